@@ -1,3 +1,4 @@
+pub use crate::token::Span;
 
 #[derive(PartialEq, Clone, Debug)]
 pub struct Ident(pub String);
@@ -5,8 +6,10 @@ pub struct Ident(pub String);
 #[derive(PartialEq, Clone, Debug)]
 pub enum Literal {
     Int(i64),
+    Float(f64),
     String(String),
     Bool(bool),
+    Null,
     Array(Vec<Expr>),
     Hash(Vec<(Expr, Expr)>),
 }
@@ -17,6 +20,7 @@ pub enum Prefix {
     Plus,
     Minus,
     Not,
+    BitNot,
 }
 
 impl std::fmt::Display for Prefix {
@@ -25,6 +29,7 @@ impl std::fmt::Display for Prefix {
             Prefix::Plus => write!(f, "+"),
             Prefix::Minus => write!(f, "-"),
             Prefix::Not => write!(f, "!"),
+            Prefix::BitNot => write!(f, "~"),
         }
     }
 }
@@ -36,12 +41,21 @@ pub enum Infix {
     Minus,
     Divide,
     Multiply,
+    Modulo,
+    Power,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
     Equal,
     NotEqual,
     GTEQ,
     GT,
     LTEQ,
     LT,
+    And,
+    Or,
 }
 
 impl std::fmt::Display for Infix {
@@ -51,12 +65,46 @@ impl std::fmt::Display for Infix {
             Infix::Minus => write!(f, "-"),
             Infix::Divide => write!(f, "/"),
             Infix::Multiply => write!(f, "*"),
+            Infix::Modulo => write!(f, "%"),
+            Infix::Power => write!(f, "**"),
+            Infix::BitAnd => write!(f, "&"),
+            Infix::BitOr => write!(f, "|"),
+            Infix::BitXor => write!(f, "^"),
+            Infix::ShiftLeft => write!(f, "<<"),
+            Infix::ShiftRight => write!(f, ">>"),
             Infix::Equal => write!(f, "=="),
             Infix::NotEqual => write!(f, "!="),
             Infix::GTEQ => write!(f, ">="),
             Infix::GT => write!(f, ">"),
             Infix::LTEQ => write!(f, "<="),
             Infix::LT => write!(f, "<"),
+            Infix::And => write!(f, "&&"),
+            Infix::Or => write!(f, "||"),
+        }
+    }
+}
+
+/// a piece of a template string
+#[derive(PartialEq, Clone, Debug)]
+pub enum TemplatePart {
+    Text(String),
+    Expr(Expr),
+}
+
+/// a parameter of a function
+#[derive(PartialEq, Clone, Debug)]
+pub enum Param {
+    Required(Ident),
+    /// `b = 1`, the expr is evaluated at each call which leaves it out
+    Default(Ident, Expr),
+    /// `...rest`, the arguments left over as an array
+    Rest(Ident),
+}
+
+impl Param {
+    pub fn ident(&self) -> &Ident {
+        match self {
+            Param::Required(ident) | Param::Default(ident, _) | Param::Rest(ident) => ident,
         }
     }
 }
@@ -64,46 +112,192 @@ impl std::fmt::Display for Infix {
 /// expr
 #[derive(PartialEq, Clone, Debug)]
 pub enum Expr {
-    Literal(Literal),
-    Ident(Ident),
-    Prefix(Prefix, Box<Expr>),
-    Infix(Infix, Box<Expr>, Box<Expr>),
-    Index(Box<Expr>, Box<Expr>),
+    Literal(Literal, Span),
+    Ident(Ident, Span),
+    Template(Vec<TemplatePart>, Span),
+    Prefix(Prefix, Box<Expr>, Span),
+    Infix(Infix, Box<Expr>, Box<Expr>, Span),
+    Index(Box<Expr>, Box<Expr>, Span),
     While {
         cond: Box<Expr>,
         consequence: BlockStmt,
+        span: Span,
     },
     If {
         cond: Box<Expr>,
         consequence: BlockStmt,
         alternative: Option<BlockStmt>,
+        span: Span,
     },
     Function {
-        params: Vec<Ident>,
+        params: Vec<Param>,
         body: BlockStmt,
+        span: Span,
     },
     Call {
         func: Box<Expr>,
         args: Vec<Expr>,
+        /// `name = value` arguments, bound by parameter name
+        named: Vec<(Ident, Expr)>,
+        span: Span,
     },
 }
 
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Literal(_, span)
+            | Expr::Ident(_, span)
+            | Expr::Template(_, span)
+            | Expr::Prefix(_, _, span)
+            | Expr::Infix(_, _, _, span)
+            | Expr::Index(_, _, span)
+            | Expr::While { span, .. }
+            | Expr::If { span, .. }
+            | Expr::Function { span, .. }
+            | Expr::Call { span, .. } => *span,
+        }
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub enum Stmt {
-    Let(Ident, Expr),
-    Const(Ident, Expr),
-    Break,
+    /// the Option holds the `///` doc comment written above the declaration
+    Let(Ident, Expr, Option<String>, Span),
+    Const(Ident, Expr, Option<String>, Span),
+    /// `法则 名字(x, y) { ... }`, bound before the rest of its block runs
+    Function(Ident, Vec<Param>, BlockStmt, Option<String>, Span),
+    Break(Span),
     Blank,
-    Continue,
-    Return(Expr),
-    Expr(Expr),
-    ReAssign(Ident, Expr)
+    Continue(Span),
+    Return(Expr, Span),
+    Expr(Expr, Span),
+    ReAssign(Ident, Expr, Span),
+    /// `target[index] = value`, dot-index `target.field = value` is sugar for it,
+    /// the infix is the operator of a compound assign like `target[index] += value`
+    IndexAssign(Expr, Expr, Option<Infix>, Expr, Span),
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Let(_, _, _, span)
+            | Stmt::Const(_, _, _, span)
+            | Stmt::Function(_, _, _, _, span)
+            | Stmt::Return(_, span)
+            | Stmt::Expr(_, span)
+            | Stmt::ReAssign(_, _, span)
+            | Stmt::IndexAssign(_, _, _, _, span)
+            | Stmt::Break(span)
+            | Stmt::Continue(span) => *span,
+            Stmt::Blank => Span::default(),
+        }
+    }
 }
 
 pub type BlockStmt = Vec<Stmt>;
 
 pub type Program = BlockStmt;
 
+/// the program with every span reset to Span::default(),
+/// so a parsed tree can be compared with one written out by hand
+#[cfg(test)]
+pub fn strip_spans(mut program: Program) -> Program {
+    program.iter_mut().for_each(strip_stmt);
+    program
+}
+
+#[cfg(test)]
+fn strip_stmt(stmt: &mut Stmt) {
+    match stmt {
+        Stmt::Let(_, expr, _, span) | Stmt::Const(_, expr, _, span) => {
+            strip_expr(expr);
+            *span = Span::default();
+        }
+        Stmt::Function(_, params, body, _, span) => {
+            params.iter_mut().for_each(strip_param);
+            body.iter_mut().for_each(strip_stmt);
+            *span = Span::default();
+        }
+        Stmt::Return(expr, span) | Stmt::Expr(expr, span) | Stmt::ReAssign(_, expr, span) => {
+            strip_expr(expr);
+            *span = Span::default();
+        }
+        Stmt::IndexAssign(target, index, _, expr, span) => {
+            strip_expr(target);
+            strip_expr(index);
+            strip_expr(expr);
+            *span = Span::default();
+        }
+        Stmt::Break(span) | Stmt::Continue(span) => *span = Span::default(),
+        Stmt::Blank => {}
+    }
+}
+
+#[cfg(test)]
+fn strip_param(param: &mut Param) {
+    if let Param::Default(_, expr) = param {
+        strip_expr(expr);
+    }
+}
+
+#[cfg(test)]
+fn strip_expr(expr: &mut Expr) {
+    match expr {
+        Expr::Literal(literal, span) => {
+            match literal {
+                Literal::Array(items) => items.iter_mut().for_each(strip_expr),
+                Literal::Hash(pairs) => pairs.iter_mut().for_each(|(key, value)| {
+                    strip_expr(key);
+                    strip_expr(value);
+                }),
+                _ => {}
+            }
+            *span = Span::default();
+        }
+        Expr::Ident(_, span) => *span = Span::default(),
+        Expr::Template(parts, span) => {
+            for part in parts {
+                if let TemplatePart::Expr(expr) = part {
+                    strip_expr(expr);
+                }
+            }
+            *span = Span::default();
+        }
+        Expr::Prefix(_, right, span) => {
+            strip_expr(right);
+            *span = Span::default();
+        }
+        Expr::Infix(_, left, right, span) | Expr::Index(left, right, span) => {
+            strip_expr(left);
+            strip_expr(right);
+            *span = Span::default();
+        }
+        Expr::While { cond, consequence, span } => {
+            strip_expr(cond);
+            consequence.iter_mut().for_each(strip_stmt);
+            *span = Span::default();
+        }
+        Expr::If { cond, consequence, alternative, span } => {
+            strip_expr(cond);
+            consequence.iter_mut().for_each(strip_stmt);
+            alternative.iter_mut().flatten().for_each(strip_stmt);
+            *span = Span::default();
+        }
+        Expr::Function { params, body, span } => {
+            params.iter_mut().for_each(strip_param);
+            body.iter_mut().for_each(strip_stmt);
+            *span = Span::default();
+        }
+        Expr::Call { func, args, named, span } => {
+            strip_expr(func);
+            args.iter_mut().for_each(strip_expr);
+            named.iter_mut().for_each(|(_, expr)| strip_expr(expr));
+            *span = Span::default();
+        }
+    }
+}
+
 
 ///
 // Precedence low -> high
@@ -112,11 +306,18 @@ pub type Program = BlockStmt;
 #[derive(PartialEq, PartialOrd, Debug, Clone)]
 pub enum Precedence {
     Lowest,
+    LogicalOr,   // ||
+    LogicalAnd,  // &&
     Equals,      // ==
     LessGreater, // > or <
+    BitOr,       // |
+    BitXor,      // ^
+    BitAnd,      // &
+    Shift,       // << or >>
     Sum,         // +
-    Product,     // *
-    Prefix,      // -X or !X
+    Product,     // * or / or %
+    Prefix,      // -X or !X or ~X
+    Power,       // ** (right associative, binds tighter than prefix on its left)
     Call,        // myFunction(x)
     Index,       // array[index]
 }
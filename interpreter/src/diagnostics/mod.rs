@@ -0,0 +1,378 @@
+use crate::parser::ParseError;
+use crate::lexer::half_width;
+use crate::token::{radix_name, LexError, Span, Token};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// A human readable error pointing into the source
+/// (诊断信息: 指向源码具体位置的可读错误)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Option<Span>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: String) -> Self {
+        Diagnostic {
+            message,
+            span: None,
+            help: None,
+        }
+    }
+
+    pub fn with_span(mut self, span: Option<Span>) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn with_help(mut self, help: String) -> Self {
+        self.help = Some(help);
+        self
+    }
+
+    /// render without any color, suitable for logs and tests
+    pub fn render_plain(&self, file_name: &str, source: &str) -> String {
+        self.render(file_name, source, false)
+    }
+
+    /// render with ansi colors, suitable for terminals
+    pub fn render_colored(&self, file_name: &str, source: &str) -> String {
+        self.render(file_name, source, true)
+    }
+
+    pub fn render(&self, file_name: &str, source: &str, colored: bool) -> String {
+        let paint = |color: &str, text: &str| {
+            if colored {
+                format!("{}{}{}", color, text, RESET)
+            } else {
+                text.to_string()
+            }
+        };
+
+        let mut result = format!("{}{}", paint(RED, "error"), paint(BOLD, &format!(": {}", self.message)));
+
+        // split, not lines, so the end of input after a trailing newline still has its (empty) line
+        let excerpt = self.span.and_then(|span| {
+            let line_text = source.split('\n').nth(span.line.checked_sub(1)?)?.trim_end_matches('\r');
+            let line_start = line_start_offset(source, span.line);
+            if span.start < line_start || span.start > line_start + line_text.len() || span.end > source.len() {
+                return None;
+            }
+            Some((span, line_text, line_start))
+        });
+        let (span, line_text, line_start) = match excerpt {
+            Some(excerpt) => excerpt,
+            // a span outside of source keeps its location but gets no excerpt
+            None => {
+                result.push_str(&format!("\n {} {}", paint(BLUE, "-->"), file_name));
+                if let Some(span) = self.span {
+                    result.push_str(&format!(":{}:{}", span.line, span.column));
+                }
+                if let Some(help) = &self.help {
+                    result.push_str(&format!("\n {} {}", paint(BLUE, "="), paint(CYAN, &format!("help: {}", help))));
+                }
+                return result;
+            }
+        };
+
+        let line_number = span.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+
+        // the underline starts at column and stops at the end of span (or line)
+        let mut padding = String::new();
+        let mut width = 0;
+        for (offset, ch) in line_text.char_indices() {
+            let offset = line_start + offset;
+            if offset < span.start {
+                padding.push_str(&if ch == '\t' { "\t".to_string() } else { " ".repeat(char_width(ch)) });
+            } else if offset < span.end {
+                width += char_width(ch);
+            } else {
+                break;
+            }
+        }
+        let underline = "^".repeat(std::cmp::max(width, 1));
+
+        result.push_str(&format!(
+            "\n{}{} {}:{}:{}",
+            gutter,
+            paint(BLUE, "-->"),
+            file_name,
+            span.line,
+            span.column
+        ));
+        result.push_str(&format!("\n{} {}", gutter, paint(BLUE, "|")));
+        result.push_str(&format!("\n{} {}", paint(BLUE, &line_number), paint(BLUE, "|")));
+        if !line_text.is_empty() {
+            result.push_str(&format!(" {}", line_text));
+        }
+        result.push_str(&format!("\n{} {} {}{}", gutter, paint(BLUE, "|"), padding, paint(RED, &underline)));
+        if let Some(help) = &self.help {
+            result.push_str(&format!("\n{} {} {}", gutter, paint(BLUE, "="), paint(CYAN, &format!("help: {}", help))));
+        }
+
+        result
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        match error {
+            ParseError::UnexpectedToken { want: Some(want), got, span } => {
+                let diagnostic = Diagnostic::error(format!("expected `{}`, found {}", want, describe(got)))
+                    .with_span(Some(*span));
+                match want {
+                    Token::RParen | Token::RBrace | Token::RBracket => {
+                        diagnostic.with_help(format!("add the missing `{}`", want))
+                    }
+                    _ => diagnostic,
+                }
+            }
+            ParseError::UnexpectedToken { want: None, got, span } => {
+                Diagnostic::error(format!("unexpected {}", describe(got))).with_span(Some(*span))
+            }
+            ParseError::Lex { error, span } => {
+                let diagnostic = Diagnostic::error(error.to_string()).with_span(Some(*span));
+                match error {
+                    LexError::UnterminatedString(quote) => {
+                        diagnostic.with_help(format!("add the closing `{}`", quote))
+                    }
+                    LexError::UnterminatedComment => {
+                        diagnostic.with_help(String::from("add the closing `*/`"))
+                    }
+                    LexError::InvalidNumeral(_) => diagnostic.with_help(String::from(
+                        "write numbers like 十一, 二百零五 or 一万, a word made only of numeral chars is always a number",
+                    )),
+                    LexError::InvalidDigit(literal, _) => diagnostic.with_help(format!(
+                        "{} literals only use the digits {}",
+                        radix_name(literal),
+                        match radix_name(literal) {
+                            "hexadecimal" => "0-9 and a-f",
+                            "octal" => "0-7",
+                            _ => "0 and 1",
+                        }
+                    )),
+                    LexError::TrailingUnderscore(_) => {
+                        diagnostic.with_help(String::from("a `_` can only sit between digits, like 1_000"))
+                    }
+                    LexError::MissingDigits(literal) => {
+                        diagnostic.with_help(format!("add digits, like `{}1`", &literal[..2]))
+                    }
+                    LexError::FullWidth(c) => match half_width(*c) {
+                        Some(' ') => diagnostic.with_help(String::from("use an ascii space instead")),
+                        Some(ascii) => diagnostic.with_help(format!("use `{}` instead", ascii)),
+                        None => diagnostic,
+                    },
+                    LexError::InvalidEscape(_) => diagnostic.with_help(String::from(
+                        "valid escapes are \\n \\t \\r \\0 \\\\ \\\" \\' \\` \\$ and \\u{...}",
+                    )),
+                    _ => diagnostic,
+                }
+            }
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Eof | Token::Illegal | Token::Blank => token.to_string(),
+        _ => format!("`{}`", token),
+    }
+}
+
+fn line_start_offset(source: &str, line: usize) -> usize {
+    if line <= 1 {
+        return 0;
+    }
+    source
+        .match_indices('\n')
+        .nth(line - 2)
+        .map(|(offset, _)| offset + 1)
+        .unwrap_or(source.len())
+}
+
+/// columns taken by a char in a terminal, wide for CJK and full-width forms
+fn char_width(ch: char) -> usize {
+    match ch as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse_diagnostics(input: &str) -> Vec<Diagnostic> {
+        let mut parser = Parser::new(Lexer::new(input));
+        parser.parse();
+        parser.get_errors().iter().map(Diagnostic::from).collect()
+    }
+
+    #[test]
+    fn test_render_parse_error() {
+        let input = "给 三体 以 1;\n给 罗辑 ＃ 2;";
+        let diagnostics = parse_diagnostics(input);
+
+        assert_eq!(
+            diagnostics[0].render_plain("main.3body", input),
+            "error: expected `=`, found illegal character
+ --> main.3body:2:6
+  |
+2 | 给 罗辑 ＃ 2;
+  |         ^^"
+        );
+    }
+
+    #[test]
+    fn test_render_help() {
+        let input = "广播(1";
+        let diagnostics = parse_diagnostics(input);
+
+        assert_eq!(
+            diagnostics[0].render_plain("<repl>", input),
+            "error: expected `)`, found end of input
+ --> <repl>:1:5
+  |
+1 | 广播(1
+  |       ^
+  = help: add the missing `)`"
+        );
+    }
+
+    #[test]
+    fn test_render_full_width_in_strict_mode() {
+        let input = "广播【1】";
+        let mut lexer = Lexer::new(input);
+        lexer.set_strict(true);
+        let mut parser = Parser::new(lexer);
+        parser.parse();
+        let diagnostics: Vec<Diagnostic> = parser.get_errors().iter().map(Diagnostic::from).collect();
+
+        assert_eq!(
+            diagnostics[0].render_plain("<repl>", input),
+            "error: full-width `【` is not allowed in strict mode
+ --> <repl>:1:3
+  |
+1 | 广播【1】
+  |     ^^
+  = help: use `[` instead"
+        );
+    }
+
+    #[test]
+    fn test_render_invalid_digit() {
+        let input = "给 掩码 以 0b102;";
+        let diagnostics = parse_diagnostics(input);
+
+        assert_eq!(
+            diagnostics[0].render_plain("<repl>", input),
+            "error: invalid digit `2` in binary literal `0b102`
+ --> <repl>:1:8
+  |
+1 | 给 掩码 以 0b102;
+  |            ^^^^^
+  = help: binary literals only use the digits 0 and 1"
+        );
+    }
+
+    #[test]
+    fn test_render_unterminated_string() {
+        let input = "给 三体 以 \"3";
+        let diagnostics = parse_diagnostics(input);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].render_plain("<repl>", input),
+            "error: unterminated string
+ --> <repl>:1:8
+  |
+1 | 给 三体 以 \"3
+  |            ^^
+  = help: add the closing `\"`"
+        );
+    }
+
+    #[test]
+    fn test_invalid_escape_help() {
+        let input = "给 a 以 `\\q`;";
+        let diagnostics = parse_diagnostics(input);
+
+        assert_eq!(
+            diagnostics[0].help.as_deref(),
+            Some("valid escapes are \\n \\t \\r \\0 \\\\ \\\" \\' \\` \\$ and \\u{...}")
+        );
+    }
+
+    #[test]
+    fn test_render_without_span() {
+        let diagnostic = Diagnostic::error(String::from("identifier not found: 叶文洁"));
+        assert_eq!(
+            diagnostic.render_plain("<repl>", ""),
+            "error: identifier not found: 叶文洁\n --> <repl>"
+        );
+    }
+
+    #[test]
+    fn test_render_span_outside_source() {
+        let diagnostic = Diagnostic::error(String::from("identifier not found: 叶文洁"));
+        for span in [Span::new(0, 3, 0, 1), Span::new(20, 23, 3, 1), Span::new(4, 40, 1, 5)] {
+            assert_eq!(
+                diagnostic.clone().with_span(Some(span)).render_plain("<repl>", "广播(1)"),
+                format!("error: identifier not found: 叶文洁\n --> <repl>:{}:{}", span.line, span.column)
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_end_of_input() {
+        let input = "广播(1\n";
+        let diagnostics = parse_diagnostics(input);
+
+        assert_eq!(
+            diagnostics[0].render_plain("main.3body", input),
+            "error: expected `)`, found end of input
+ --> main.3body:2:1
+  |
+2 |
+  | ^
+  = help: add the missing `)`"
+        );
+    }
+
+    #[test]
+    fn test_render_colored() {
+        let input = "1 +";
+        let rendered = parse_diagnostics(input)[0].render_colored("<repl>", input);
+        assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m"));
+    }
+
+    #[test]
+    fn test_char_width() {
+        assert_eq!(char_width('a'), 1);
+        assert_eq!(char_width('三'), 2);
+        assert_eq!(char_width('！'), 2);
+    }
+}
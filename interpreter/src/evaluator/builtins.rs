@@ -7,9 +7,11 @@ extern crate rand;
 
 use crate::evaluator::object::Object;
 use crate::evaluator::object::NativeObject;
+use crate::evaluator::object::OrderedHash;
+use crate::lexer::numeral::to_numeral;
+use crate::token::keyword::KeywordSet;
 use crate::evaluator::env::Env;
 use crate::evaluator::Evaluator;
-use crate::ast;
 
 use rand::distributions::Uniform;
 use rand::{thread_rng, Rng};
@@ -45,6 +47,10 @@ pub fn new_builtins() -> HashMap<String, Object> {
         String::from("没关系的都一样"),
         Object::Builtin(2, three_body_deep_equal),
     );
+    builtins.insert(
+        String::from("中文数字"),
+        Object::Builtin(1, three_body_chinese_numeral),
+    );
     #[cfg(feature="sophon")]
     builtins.insert(
         String::from("智子工程"),
@@ -58,11 +64,22 @@ pub fn new_builtins() -> HashMap<String, Object> {
     builtins
 }
 
+/// the builtins plus the aliases a locale pack gives them
+pub fn new_builtins_with(keywords: &KeywordSet) -> HashMap<String, Object> {
+    let mut builtins = new_builtins();
+    for (alias, builtin) in keywords.builtin_aliases() {
+        if let Some(func) = builtins.get(builtin).cloned() {
+            builtins.insert(alias.to_string(), func);
+        }
+    }
+    builtins
+}
+
 fn monkey_len(args: Vec<Object>) -> Object {
     match &args[0] {
         Object::String(s) => Object::Int(s.len() as i64),
         Object::Array(o) => Object::Int(o.len() as i64),
-        o => Object::Error(format!("argument to `len` not supported, got {}", o)),
+        o => Object::Error(format!("argument to `len` not supported, got {}", o.repr())),
     }
 }
 
@@ -75,7 +92,7 @@ fn monkey_first(args: Vec<Object>) -> Object {
                 Object::Null
             }
         }
-        o => Object::Error(format!("argument to `first` must be array. got {}", o)),
+        o => Object::Error(format!("argument to `first` must be array. got {}", o.repr())),
     }
 }
 
@@ -88,7 +105,7 @@ fn monkey_last(args: Vec<Object>) -> Object {
                 Object::Null
             }
         }
-        o => Object::Error(format!("argument to `last` must be array. got {}", o)),
+        o => Object::Error(format!("argument to `last` must be array. got {}", o.repr())),
     }
 }
 
@@ -101,7 +118,7 @@ fn monkey_rest(args: Vec<Object>) -> Object {
                 Object::Null
             }
         }
-        o => Object::Error(format!("argument to `rest` must be array. got {}", o)),
+        o => Object::Error(format!("argument to `rest` must be array. got {}", o.repr())),
     }
 }
 
@@ -112,7 +129,7 @@ fn monkey_push(args: Vec<Object>) -> Object {
             arr.push(args[1].clone());
             Object::Array(arr)
         }
-        o => Object::Error(format!("argument to `push` must be array. got {}", o)),
+        o => Object::Error(format!("argument to `push` must be array. got {}", o.repr())),
     }
 }
 
@@ -156,10 +173,13 @@ fn three_body_random(args: Vec<Object>) -> Object {
 }
 
 fn three_body_deep_equal(args: Vec<Object>) -> Object {
-    if format!("{}", &args[0]) == format!("{}", &args[1]) {
-        Object::Bool(true)
-    } else {
-        Object::Bool(false)
+    Object::Bool(args[0].deep_eq(&args[1]))
+}
+
+fn three_body_chinese_numeral(args: Vec<Object>) -> Object {
+    match &args[0] {
+        Object::Int(value) => Object::String(to_numeral(*value)),
+        o => Object::Error(format!("argument to `中文数字` must be integer. got {}", o.repr())),
     }
 }
 
@@ -167,9 +187,15 @@ fn three_body_deep_equal(args: Vec<Object>) -> Object {
 fn three_body_sophon_engineering(args: Vec<Object>) -> Object {
     match &args[0] {
         Object::Hash(o) => {
-            let model_type = o[&Object::String("type".to_owned())].clone();
-            let model_path = o[&Object::String("path".to_owned())].clone();
-            let prompt = o[&Object::String("prompt".to_owned())].clone();
+            let field = |key: &str| o.get(&Object::String(key.to_owned())).cloned();
+            let (model_type, model_path, prompt) = match (field("type"), field("path"), field("prompt")) {
+                (Some(model_type), Some(model_path), Some(prompt)) => (model_type, model_path, prompt),
+                _ => {
+                    return Object::Error(String::from(
+                        "argument to `智子工程` must have \"type\", \"path\" and \"prompt\"",
+                    ))
+                }
+            };
 
             let now = std::time::Instant::now();
 
@@ -231,7 +257,7 @@ fn three_body_sophon_engineering(args: Vec<Object>) -> Object {
 
             let model_ptr = &mut *model as *mut dyn Model;
 
-            let mut session_hash = HashMap::new();
+            let mut session_hash = OrderedHash::new();
             session_hash.insert(Object::String("model".to_owned()), Object::Native(Box::new(NativeObject::LLMModel(model_ptr))));
             session_hash.insert(Object::String("character".to_owned()), Object::String(character.to_string()));
 
@@ -352,7 +378,7 @@ fn three_body_sophon_engineering(args: Vec<Object>) -> Object {
 
 #[cfg(feature="threading")]
 fn three_body_threading(_: Vec<Object>) -> Object {
-    let mut session_hash = HashMap::new();
+    let mut session_hash = OrderedHash::new();
     {
         fn three_body_thread_new(args: Vec<Object>) -> Object {
             let handle = std::thread::spawn(|| {
@@ -364,24 +390,19 @@ fn three_body_threading(_: Vec<Object>) -> Object {
 
                 local_set.spawn_local(async move {
                     match &args[0] {
-                        Object::Function(params, stmts, env ) => {
-                            let mut ev = Evaluator {
-                                env: {
-                                    let mut scoped_env = Env::new_with_outer(Rc::clone(&env)); // still thread unsafe
-                                        let list = params.iter().zip({
-                                        match &args[1] {
-                                            Object::Array(arr) => arr,
-                                            _ => panic!()
-                                        }
-                                    }.iter());
-                                    for (_, (ident, o)) in list.enumerate() {
-                                        let ast::Ident(name) = ident.clone();
-                                        scoped_env.set(name, o.clone());
-                                    }
-                                    Rc::new(RefCell::new(scoped_env))
-                                },
+                        Object::Function(function) => {
+                            let arr = match &args[1] {
+                                Object::Array(arr) => arr.clone(),
+                                _ => panic!()
                             };
-                            ev.eval(&stmts);
+                            let scoped_env = Env::new_with_outer(Rc::clone(&function.env)); // still thread unsafe
+                            let mut ev = Evaluator::new(Rc::new(RefCell::new(scoped_env)));
+                            // bound the same way as in an ordinary call
+                            if let Some(Object::Error(msg)) = ev.bind_args(&function.params, arr, vec![], &function.name) {
+                                eprintln!("{}", msg);
+                                return;
+                            }
+                            ev.eval(&function.body);
                         },
                         _ => panic!()
                     }
@@ -431,6 +452,18 @@ mod tests {
     use std::cell::RefCell;
     use std::rc::Rc;
 
+    #[test]
+    fn test_new_builtins_with() {
+        let builtins = new_builtins_with(&KeywordSet::english());
+        assert_eq!(builtins.get("print"), builtins.get("广播"));
+        assert!(builtins.contains_key("deep_equal"));
+        assert!(!builtins.contains_key("廣播"));
+
+        let mut keywords = KeywordSet::empty();
+        keywords.alias_builtin("不存在", "没有这个函数");
+        assert_eq!(new_builtins_with(&keywords), new_builtins());
+    }
+
     #[test]
     fn test_monkey_len_string() {
         let args = vec![Object::String(String::from("hello"))];
@@ -514,6 +547,23 @@ mod tests {
         assert_eq!(monkey_push(args), expected);
     }
 
+    #[test]
+    fn test_three_body_chinese_numeral() {
+        let tests = vec![
+            (Object::Int(400), Object::String(String::from("四百"))),
+            (Object::Int(205), Object::String(String::from("二百零五"))),
+            (Object::Int(-11), Object::String(String::from("负十一"))),
+            (
+                Object::Float(1.5),
+                Object::Error(String::from("argument to `中文数字` must be integer. got 1.5")),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(three_body_chinese_numeral(vec![input]), expected);
+        }
+    }
+
     #[test]
     fn test_three_body_deep_equal() {
         let tests = vec![
@@ -578,12 +628,12 @@ mod tests {
             (
                 vec![
                     {
-                        let mut hash = HashMap::new();
+                        let mut hash = OrderedHash::new();
                         hash.insert(Object::String("a".to_string()), Object::Int(1));
                         Object::Hash(hash)
                     },
                     {
-                        let mut hash = HashMap::new();
+                        let mut hash = OrderedHash::new();
                         hash.insert(Object::String("a".to_string()), Object::Int(1));
                         Object::Hash(hash)
                     },
@@ -593,12 +643,12 @@ mod tests {
             (
                 vec![
                     {
-                        let mut hash = HashMap::new();
+                        let mut hash = OrderedHash::new();
                         hash.insert(Object::String("a".to_string()), Object::Int(1));
                         Object::Hash(hash)
                     },
                     {
-                        let mut hash = HashMap::new();
+                        let mut hash = OrderedHash::new();
                         hash.insert(Object::String("b".to_string()), Object::Int(2));
                         Object::Hash(hash)
                     },
@@ -612,32 +662,54 @@ mod tests {
                 ],
                 Object::Bool(true),
             ),
+            (
+                {
+                    let env = Rc::new(RefCell::new(Env::new()));
+                    let function = Object::function(vec![ast::Param::Required(ast::Ident(String::from("x")))], vec![], Rc::clone(&env), None);
+                    vec![function.clone(), function]
+                },
+                Object::Bool(true),
+            ),
+            (
+                {
+                    let env = Rc::new(RefCell::new(Env::new()));
+                    vec![
+                        Object::function(vec![ast::Param::Required(ast::Ident(String::from("x")))], vec![], Rc::clone(&env), None),
+                        Object::function(vec![ast::Param::Required(ast::Ident(String::from("x")))], vec![], Rc::clone(&env), None),
+                    ]
+                },
+                Object::Bool(false),
+            ),
             (
                 vec![
-                    Object::Function(
-                        vec![ast::Ident(String::from("x"))],
+                    Object::function(
+                        vec![ast::Param::Required(ast::Ident(String::from("x")))],
                         vec![],
                         Rc::new(RefCell::new(Env::new())),
+                        None,
                     ),
-                    Object::Function(
-                        vec![ast::Ident(String::from("x"))],
+                    Object::function(
+                        vec![ast::Param::Required(ast::Ident(String::from("x")))],
                         vec![],
                         Rc::new(RefCell::new(Env::new())),
+                        None,
                     ),
                 ],
-                Object::Bool(true),
+                Object::Bool(false),
             ),
             (
                 vec![
-                    Object::Function(
-                        vec![ast::Ident(String::from("x"))],
+                    Object::function(
+                        vec![ast::Param::Required(ast::Ident(String::from("x")))],
                         vec![],
                         Rc::new(RefCell::new(Env::new())),
+                        None,
                     ),
-                    Object::Function(
-                        vec![ast::Ident(String::from("y"))],
+                    Object::function(
+                        vec![ast::Param::Required(ast::Ident(String::from("y")))],
                         vec![],
                         Rc::new(RefCell::new(Env::new())),
+                        None,
                     ),
                 ],
                 Object::Bool(false),
@@ -645,10 +717,11 @@ mod tests {
             (
                 vec![
                     Object::Builtin(1, monkey_first),
-                    Object::Function(
-                        vec![ast::Ident(String::from("x"))],
+                    Object::function(
+                        vec![ast::Param::Required(ast::Ident(String::from("x")))],
                         vec![],
                         Rc::new(RefCell::new(Env::new())),
+                        None,
                     ),
                 ],
                 Object::Bool(false),
@@ -657,6 +730,40 @@ mod tests {
                 vec![Object::Int(1), Object::String(String::from("2"))],
                 Object::Bool(false),
             ),
+            (
+                vec![Object::Int(1), Object::String(String::from("1"))],
+                Object::Bool(false),
+            ),
+            (
+                vec![
+                    Object::Array(vec![Object::String(String::from("1, 2"))]),
+                    Object::Array(vec![Object::Int(1), Object::Int(2)]),
+                ],
+                Object::Bool(false),
+            ),
+            (vec![Object::Int(1), Object::Float(1.0)], Object::Bool(false)),
+            (vec![Object::Float(f64::NAN), Object::Float(f64::NAN)], Object::Bool(true)),
+            (vec![Object::Null, Object::Null], Object::Bool(true)),
+            (vec![Object::Null, Object::Bool(false)], Object::Bool(false)),
+            (
+                vec![
+                    {
+                        let mut hash = OrderedHash::new();
+                        for i in 0..32 {
+                            hash.insert(Object::Int(i), Object::Int(i * i));
+                        }
+                        Object::Hash(hash)
+                    },
+                    {
+                        let mut hash = OrderedHash::new();
+                        for i in (0..32).rev() {
+                            hash.insert(Object::Int(i), Object::Int(i * i));
+                        }
+                        Object::Hash(hash)
+                    },
+                ],
+                Object::Bool(true),
+            ),
         ];
 
         for (input, expected) in tests {
@@ -90,6 +90,24 @@ impl Env {
         }
     }
 
+    /// what `update` would answer for this name, without writing anything
+    pub fn check_update(&mut self, name: String) -> UpdateInfo {
+        match self.identifiers.contains_key(&name) {
+            true => {
+                if self.is_constant(name.clone()) {
+                    return UpdateInfo::ConstantForbidden;
+                }
+                UpdateInfo::Succeed
+            },
+            false => {
+                match self.outer {
+                    Some(ref outer) => outer.borrow_mut().check_update(name),
+                    None => UpdateInfo::NoIdentifier,
+                }
+            }
+        }
+    }
+
     pub fn get(&mut self, name: String) -> Option<Object> {
         match self.identifiers.get(&name) {
             Some(value) => Some(value.clone()),
@@ -191,6 +209,19 @@ mod tests {
         assert_eq!(env.update("key".to_string(), Object::Int(2)), UpdateInfo::NoIdentifier);
     }
 
+    #[test]
+    fn test_check_update() {
+        let outer = Rc::new(RefCell::new(Env::new()));
+        outer.as_ref().borrow_mut().set("key".to_string(), Object::Int(1));
+        outer.as_ref().borrow_mut().set("const".to_string(), Object::Int(1));
+        outer.as_ref().borrow_mut().constant("const".to_string());
+        let mut env = Env::new_with_outer(outer.clone());
+        assert_eq!(env.check_update("key".to_string()), UpdateInfo::Succeed);
+        assert_eq!(env.check_update("const".to_string()), UpdateInfo::ConstantForbidden);
+        assert_eq!(env.check_update("none".to_string()), UpdateInfo::NoIdentifier);
+        assert_eq!(outer.as_ref().borrow_mut().get("key".to_string()), Some(Object::Int(1)));
+    }
+
     #[test]
     fn test_update_out_identifier() {
         let global = Rc::new(RefCell::new(Env::new()));
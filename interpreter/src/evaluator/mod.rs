@@ -9,6 +9,10 @@ use crate::ast;
 #[derive(PartialEq, Clone, Debug)]
 pub struct Evaluator {
     pub env: Rc<RefCell<env::Env>>,
+    /// span of the innermost stmt which produced the last runtime error
+    pub error_span: Option<ast::Span>,
+    /// whether the last runtime error already names the function it escaped from
+    error_named: bool,
 }
 
 ///
@@ -16,16 +20,28 @@ pub struct Evaluator {
 ///
 impl Evaluator {
     pub fn new(env: Rc<RefCell<env::Env>>) -> Self {
-        Evaluator { env }
+        Evaluator {
+            env,
+            error_span: None,
+            error_named: false,
+        }
     }
 
     pub fn eval(&mut self, program: &ast::Program) -> Option<object::Object> {
         let mut result = None;
 
+        self.error_span = None;
+        self.error_named = false;
+        if let Some(error) = self.hoist_functions(program) {
+            return Some(error);
+        }
+
         for stmt in program {
             if *stmt == ast::Stmt::Blank {
                 continue;
             }
+            self.error_span = None;
+            self.error_named = false;
             match self.eval_stmt(stmt) {
                 Some(object::Object::ReturnValue(value)) => return Some(*value),
                 Some(object::Object::Error(msg)) => return Some(object::Object::Error(msg)),
@@ -36,8 +52,18 @@ impl Evaluator {
     }
 
     fn eval_stmt(&mut self, stmt: &ast::Stmt) -> Option<object::Object> {
+        let result = self.eval_stmt_inner(stmt);
+        if let Some(object::Object::Error(_)) = result {
+            if self.error_span.is_none() {
+                self.error_span = Some(stmt.span());
+            }
+        }
+        result
+    }
+
+    fn eval_stmt_inner(&mut self, stmt: &ast::Stmt) -> Option<object::Object> {
         match stmt {
-            ast::Stmt::Let(ident, expr) => {
+            ast::Stmt::Let(ident, expr, _, _) => {
                 let value = match self.eval_expr(expr) {
                     Some(value) => value,
                     None => return None,
@@ -63,7 +89,7 @@ impl Evaluator {
                     }
                 }
             }
-            ast::Stmt::Const(ident, expr) => {
+            ast::Stmt::Const(ident, expr, _, _) => {
                 let value = match self.eval_expr(expr) {
                     Some(value) => value,
                     None => return None,
@@ -90,38 +116,47 @@ impl Evaluator {
                     }
                 }
             }
-            ast::Stmt::Break => Some(object::Object::BreakStatement),
-            ast::Stmt::Continue => Some(object::Object::ContinueStatement),
-            ast::Stmt::Return(expr) => {
+            // bound by hoist_functions before its block started
+            ast::Stmt::Function(..) => None,
+            ast::Stmt::Break(_) => Some(object::Object::BreakStatement),
+            ast::Stmt::Continue(_) => Some(object::Object::ContinueStatement),
+            ast::Stmt::Return(expr, _) => {
                 let value = match self.eval_expr(expr) {
                     Some(value) => value,
                     None => return None,
                 };
+                // an error is not a value, it goes up as itself so the callers can name the function
+                if let object::Object::Error(_) = value {
+                    return Some(value);
+                }
                 Some(object::Object::ReturnValue(Box::new(value)))
             }
-            ast::Stmt::Expr(expr) => self.eval_expr(expr),
-            ast::Stmt::ReAssign(ident, expr) => {
-                let value = match self.eval_expr(expr) {
-                    Some(value) => value,
-                    None => return None,
-                };
+            ast::Stmt::Expr(expr, _) => self.eval_expr(expr),
+            ast::Stmt::ReAssign(ident, expr, _) => {
+                let ast::Ident(name) = ident;
+                let status = self.env.borrow_mut().check_update(name.clone());
+                if let Some(error) = Self::update_error(status, name) {
+                    return Some(error);
+                }
+                let value = self.eval_expr(expr)?;
                 if Self::is_error(&value) {
                     Some(value)
                 } else {
-                    let ast::Ident(name) = ident;
-                    match self.env.borrow_mut().update(name.clone(), value) {
-                        env::UpdateInfo::ConstantForbidden => Some(object::Object::Error(format!(
-                            "{} {}!",
-                            "Can not assign to constant variable", name
-                        ))),
-                        env::UpdateInfo::NoIdentifier => Some(object::Object::Error(format!(
-                            "{} {}!",
-                            "No identifier", name
-                        ))),
-                        env::UpdateInfo::Succeed => None,
-                    }
+                    let status = self.env.borrow_mut().update(name.clone(), value);
+                    Self::update_error(status, name)
                 }
             }
+            ast::Stmt::IndexAssign(target, index, infix, expr, _) => {
+                let name = match Self::assign_root(target) {
+                    Some(name) => name,
+                    None => return Some(Self::error(String::from("invalid assignment target"))),
+                };
+                let status = self.env.borrow_mut().check_update(name.clone());
+                if let Some(error) = Self::update_error(status, &name) {
+                    return Some(error);
+                }
+                self.eval_index_assign(&name, target, index, infix, expr)
+            }
             _ => todo!(),
         }
     }
@@ -142,6 +177,21 @@ impl Evaluator {
         }
     }
 
+    /// ReAssign and IndexAssign fail the same way when the target can not be updated
+    fn update_error(status: env::UpdateInfo, name: &str) -> Option<object::Object> {
+        match status {
+            env::UpdateInfo::ConstantForbidden => Some(object::Object::Error(format!(
+                "{} {}!",
+                "Can not assign to constant variable", name
+            ))),
+            env::UpdateInfo::NoIdentifier => Some(object::Object::Error(format!(
+                "{} {}!",
+                "No identifier", name
+            ))),
+            env::UpdateInfo::Succeed => None,
+        }
+    }
+
     fn is_error(obj: &object::Object) -> bool {
         match obj {
             object::Object::Error(_) => true,
@@ -202,6 +252,7 @@ impl Evaluator {
                 Some(object::Object::ReturnValue(value)) => {
                     return Some(object::Object::ReturnValue(value))
                 }
+                Some(object::Object::Error(msg)) => return Some(object::Object::Error(msg)),
                 _ => {}
             }
         }
@@ -215,6 +266,10 @@ impl Evaluator {
     ) -> Option<object::Object> {
         let mut result = None;
 
+        if let Some(error) = self.hoist_functions(stmts) {
+            return Some(error);
+        }
+
         for stmt in stmts {
             if *stmt == ast::Stmt::Blank {
                 continue;
@@ -243,6 +298,10 @@ impl Evaluator {
     fn eval_block_stmt(&mut self, stmts: &ast::BlockStmt) -> Option<object::Object> {
         let mut result = None;
 
+        if let Some(error) = self.hoist_functions(stmts) {
+            return Some(error);
+        }
+
         for stmt in stmts {
             if *stmt == ast::Stmt::Blank {
                 continue;
@@ -266,12 +325,15 @@ impl Evaluator {
 impl Evaluator {
     fn eval_expr(&mut self, expr: &ast::Expr) -> Option<object::Object> {
         match expr {
-            ast::Expr::Ident(ident) => Some(self.eval_ident(ident)),
-            ast::Expr::Literal(literal) => Some(self.eval_literal(literal)),
-            ast::Expr::Prefix(prefix, right_expr) => self
+            ast::Expr::Ident(ident, _) => Some(self.eval_ident(ident)),
+            ast::Expr::Literal(literal, _) => Some(self.eval_literal(literal)),
+            ast::Expr::Prefix(prefix, right_expr, _) => self
                 .eval_expr(&*right_expr)
                 .map(|right| self.eval_prefix_expr(prefix, right)),
-            ast::Expr::Infix(infix, left_expr, right_expr) => {
+            ast::Expr::Infix(infix @ (ast::Infix::And | ast::Infix::Or), left_expr, right_expr, _) => {
+                self.eval_logical_expr(infix, left_expr, right_expr)
+            }
+            ast::Expr::Infix(infix, left_expr, right_expr, _) => {
                 let left = self.eval_expr(&*left_expr);
                 let right = self.eval_expr(&*right_expr);
                 if left.is_some() && right.is_some() {
@@ -280,7 +342,7 @@ impl Evaluator {
                     None
                 }
             }
-            ast::Expr::Index(left_expr, index_expr) => {
+            ast::Expr::Index(left_expr, index_expr, _) => {
                 let left = self.eval_expr(&*left_expr);
                 let index = self.eval_expr(&*index_expr);
                 if left.is_some() && index.is_some() {
@@ -289,27 +351,54 @@ impl Evaluator {
                     None
                 }
             }
-            ast::Expr::While { cond, consequence } => self.eval_while_expr(&*cond, consequence),
+            ast::Expr::While { cond, consequence, .. } => self.eval_while_expr(&*cond, consequence),
             ast::Expr::If {
                 cond,
                 consequence,
                 alternative,
+                ..
             } => self.eval_if_expr(&*cond, consequence, alternative),
-            ast::Expr::Function { params, body } => Some(object::Object::Function(
+            ast::Expr::Function { params, body, .. } => Some(object::Object::function(
                 params.clone(),
                 body.clone(),
                 Rc::clone(&self.env),
+                None,
             )),
-            ast::Expr::Call { func, args } => Some(self.eval_call_expr(func, args)),
+            ast::Expr::Call {
+                func, args, named, ..
+            } => Some(self.eval_call_expr(func, args, named)),
+            ast::Expr::Template(parts, _) => self.eval_template_expr(parts),
             _ => None,
         }
     }
 
+    fn eval_template_expr(&mut self, parts: &[ast::TemplatePart]) -> Option<object::Object> {
+        let mut result = String::new();
+        for part in parts {
+            match part {
+                ast::TemplatePart::Text(text) => result.push_str(text),
+                ast::TemplatePart::Expr(expr) => match self.eval_expr(expr)? {
+                    object::Object::Error(message) => return Some(object::Object::Error(message)),
+                    value => result.push_str(&value.to_string()),
+                },
+            }
+        }
+        Some(object::Object::String(result))
+    }
+
     fn eval_prefix_expr(&mut self, prefix: &ast::Prefix, right: object::Object) -> object::Object {
         match prefix {
             ast::Prefix::Not => self.eval_not_op_expr(right),
             ast::Prefix::Minus => self.eval_minus_prefix_op_expr(right),
             ast::Prefix::Plus => self.eval_plus_prefix_op_expr(right),
+            ast::Prefix::BitNot => self.eval_bit_not_prefix_op_expr(right),
+        }
+    }
+
+    fn eval_bit_not_prefix_op_expr(&mut self, right: object::Object) -> object::Object {
+        match right {
+            object::Object::Int(value) => object::Object::Int(!value),
+            _ => Self::error(format!("unknown operator: ~{}", right.repr())),
         }
     }
 
@@ -324,15 +413,20 @@ impl Evaluator {
 
     fn eval_minus_prefix_op_expr(&mut self, right: object::Object) -> object::Object {
         match right {
-            object::Object::Int(value) => object::Object::Int(-value),
-            _ => Self::error(format!("unknown operator: -{}", right)),
+            object::Object::Int(value) => match value.checked_neg() {
+                Some(value) => object::Object::Int(value),
+                None => Self::error(format!("integer overflow: -{}", value)),
+            },
+            object::Object::Float(value) => object::Object::Float(-value),
+            _ => Self::error(format!("unknown operator: -{}", right.repr())),
         }
     }
 
     fn eval_plus_prefix_op_expr(&mut self, right: object::Object) -> object::Object {
         match right {
             object::Object::Int(value) => object::Object::Int(value),
-            _ => Self::error(format!("unknown operator: {}", right)),
+            object::Object::Float(value) => object::Object::Float(value),
+            _ => Self::error(format!("unknown operator: {}", right.repr())),
         }
     }
 
@@ -342,38 +436,154 @@ impl Evaluator {
         left: object::Object,
         right: object::Object,
     ) -> object::Object {
+        if Self::is_error(&left) {
+            return left;
+        }
+        if Self::is_error(&right) {
+            return right;
+        }
+
+        // structural_eq keeps the IEEE equality of floats and compares an int with a float exactly
+        match infix {
+            ast::Infix::Equal => return object::Object::Bool(left.structural_eq(&right)),
+            ast::Infix::NotEqual => return object::Object::Bool(!left.structural_eq(&right)),
+            _ => {}
+        }
+
         match left {
-            object::Object::Int(left_value) => {
-                if let object::Object::Int(right_value) = right {
+            object::Object::Int(left_value) => match right {
+                object::Object::Int(right_value) => {
                     self.eval_infix_int_expr(infix, left_value, right_value)
-                } else {
-                    Self::error(format!("type mismatch: {} {} {}", left, infix, right))
                 }
-            }
+                object::Object::Float(right_value) => {
+                    self.eval_infix_float_expr(infix, left_value as f64, right_value)
+                }
+                _ => Self::error(format!("type mismatch: {} {} {}", left.repr(), infix, right.repr())),
+            },
+            object::Object::Float(left_value) => match right {
+                object::Object::Int(right_value) => {
+                    self.eval_infix_float_expr(infix, left_value, right_value as f64)
+                }
+                object::Object::Float(right_value) => {
+                    self.eval_infix_float_expr(infix, left_value, right_value)
+                }
+                _ => Self::error(format!("type mismatch: {} {} {}", left.repr(), infix, right.repr())),
+            },
             object::Object::String(left_value) => {
                 if let object::Object::String(right_value) = right {
                     self.eval_infix_string_expr(infix, left_value, right_value)
                 } else {
-                    Self::error(format!("type mismatch: {} {} {}", left_value, infix, right))
+                    Self::error(format!(
+                        "type mismatch: {} {} {}",
+                        object::Object::String(left_value).repr(),
+                        infix,
+                        right.repr()
+                    ))
                 }
             }
-            _ => Self::error(format!("unknown operator: {} {} {}", left, infix, right)),
+            _ => Self::error(format!("unknown operator: {} {} {}", left.repr(), infix, right.repr())),
+        }
+    }
+
+    /// && and || short-circuit, and give back the operand which decides the result
+    fn eval_logical_expr(
+        &mut self,
+        infix: &ast::Infix,
+        left_expr: &ast::Expr,
+        right_expr: &ast::Expr,
+    ) -> Option<object::Object> {
+        let left = self.eval_expr(left_expr)?;
+        if Self::is_error(&left) {
+            return Some(left);
+        }
+
+        let decided = match infix {
+            ast::Infix::And => !Self::is_truthy(left.clone()),
+            _ => Self::is_truthy(left.clone()),
+        };
+
+        if decided {
+            Some(left)
+        } else {
+            self.eval_expr(right_expr)
         }
     }
 
     fn eval_infix_int_expr(&mut self, infix: &ast::Infix, left: i64, right: i64) -> object::Object {
         match infix {
-            ast::Infix::Plus => object::Object::Int(left + right),
-            ast::Infix::Minus => object::Object::Int(left - right),
-            ast::Infix::Multiply => object::Object::Int(left * right),
-            ast::Infix::Divide => object::Object::Int(left / right),
+            ast::Infix::Plus => Self::checked_int(left.checked_add(right), infix, left, right),
+            ast::Infix::Minus => Self::checked_int(left.checked_sub(right), infix, left, right),
+            ast::Infix::Multiply => Self::checked_int(left.checked_mul(right), infix, left, right),
+            ast::Infix::Divide => {
+                if right == 0 {
+                    return Self::error(format!("division by zero: {} {} {}", left, infix, right));
+                }
+                Self::checked_int(left.checked_div(right), infix, left, right)
+            }
+            ast::Infix::Modulo => {
+                if right == 0 {
+                    return Self::error(format!("division by zero: {} {} {}", left, infix, right));
+                }
+                Self::checked_int(left.checked_rem(right), infix, left, right)
+            }
+            ast::Infix::Power => {
+                if right < 0 {
+                    return object::Object::Float((left as f64).powf(right as f64));
+                }
+                let value = u32::try_from(right).ok().and_then(|exp| left.checked_pow(exp));
+                Self::checked_int(value, infix, left, right)
+            }
+            ast::Infix::BitAnd => object::Object::Int(left & right),
+            ast::Infix::BitOr => object::Object::Int(left | right),
+            ast::Infix::BitXor => object::Object::Int(left ^ right),
+            ast::Infix::ShiftLeft | ast::Infix::ShiftRight => {
+                let shift = match u32::try_from(right) {
+                    Ok(shift) if shift < i64::BITS => shift,
+                    _ => {
+                        return Self::error(format!(
+                            "shift amount out of range: {} {} {}",
+                            left, infix, right
+                        ))
+                    }
+                };
+                match infix {
+                    ast::Infix::ShiftLeft => object::Object::Int(left << shift),
+                    _ => object::Object::Int(left >> shift),
+                }
+            }
             ast::Infix::LT => object::Object::Bool(left < right),
             ast::Infix::LTEQ => object::Object::Bool(left <= right),
             ast::Infix::GT => object::Object::Bool(left > right),
             ast::Infix::GTEQ => object::Object::Bool(left >= right),
             ast::Infix::Equal => object::Object::Bool(left == right),
             ast::Infix::NotEqual => object::Object::Bool(left != right),
-            _ => todo!(),
+            _ => Self::error(format!("unknown operator: {} {} {}", left, infix, right)),
+        }
+    }
+
+    /// turn an overflowed (None) checked int result into a runtime error
+    fn checked_int(value: Option<i64>, infix: &ast::Infix, left: i64, right: i64) -> object::Object {
+        match value {
+            Some(value) => object::Object::Int(value),
+            None => Self::error(format!("integer overflow: {} {} {}", left, infix, right)),
+        }
+    }
+
+    fn eval_infix_float_expr(&mut self, infix: &ast::Infix, left: f64, right: f64) -> object::Object {
+        match infix {
+            ast::Infix::Plus => object::Object::Float(left + right),
+            ast::Infix::Minus => object::Object::Float(left - right),
+            ast::Infix::Multiply => object::Object::Float(left * right),
+            ast::Infix::Divide => object::Object::Float(left / right),
+            ast::Infix::Modulo => object::Object::Float(left % right),
+            ast::Infix::Power => object::Object::Float(left.powf(right)),
+            ast::Infix::LT => object::Object::Bool(left < right),
+            ast::Infix::LTEQ => object::Object::Bool(left <= right),
+            ast::Infix::GT => object::Object::Bool(left > right),
+            ast::Infix::GTEQ => object::Object::Bool(left >= right),
+            ast::Infix::Equal => object::Object::Bool(left == right),
+            ast::Infix::NotEqual => object::Object::Bool(left != right),
+            _ => Self::error(format!("unknown operator: {} {} {}", left, infix, right)),
         }
     }
 
@@ -385,7 +595,16 @@ impl Evaluator {
     ) -> object::Object {
         match infix {
             ast::Infix::Plus => object::Object::String(format!("{}{}", left, right)),
-            _ => object::Object::Error(format!("unknown operator: {} {} {}", left, infix, right)),
+            ast::Infix::LT => object::Object::Bool(left < right),
+            ast::Infix::LTEQ => object::Object::Bool(left <= right),
+            ast::Infix::GT => object::Object::Bool(left > right),
+            ast::Infix::GTEQ => object::Object::Bool(left >= right),
+            _ => object::Object::Error(format!(
+                "unknown operator: {} {} {}",
+                object::Object::String(left).repr(),
+                infix,
+                object::Object::String(right).repr()
+            )),
         }
     }
 }
@@ -395,14 +614,76 @@ impl Evaluator {
 // (put args ident list into scoped env and the eval block stmts with the scoped env)
 ///
 impl Evaluator {
-    fn eval_call_expr(&mut self, func: &Box<ast::Expr>, args: &Vec<ast::Expr>) -> object::Object {
+    /// bind every `法则 名字(...)` of a block before any of its stmts runs,
+    /// so functions can call each other whatever order they are written in
+    fn hoist_functions(&mut self, stmts: &[ast::Stmt]) -> Option<object::Object> {
+        for stmt in stmts {
+            let ast::Stmt::Function(ast::Ident(name), params, body, _, span) = stmt else {
+                continue;
+            };
+            let function = object::Object::function(
+                params.clone(),
+                body.clone(),
+                Rc::clone(&self.env),
+                Some(name.clone()),
+            );
+            let mut env_borrow_mut = self.env.borrow_mut();
+            let error = match env_borrow_mut.check_inner(name.clone()) {
+                env::CheckInnerInfo::ConstantExist => "Can not redeclare constant variable",
+                // a loop body is hoisted again on every pass
+                env::CheckInnerInfo::VariableExist
+                    if Self::same_declaration(env_borrow_mut.get(name.clone()), &function) =>
+                {
+                    env_borrow_mut.set(name.clone(), function);
+                    continue;
+                }
+                env::CheckInnerInfo::VariableExist => "Can not redeclare variable",
+                env::CheckInnerInfo::NoIdentifier => {
+                    env_borrow_mut.set(name.clone(), function);
+                    continue;
+                }
+            };
+            if self.error_span.is_none() {
+                self.error_span = Some(*span);
+            }
+            return Some(Self::error(format!("{} {}!", error, name)));
+        }
+        None
+    }
+
+    /// whether a bound function came from the very declaration about to be hoisted
+    fn same_declaration(bound: Option<object::Object>, function: &object::Object) -> bool {
+        match (bound, function) {
+            (Some(object::Object::Function(bound)), object::Object::Function(function)) => {
+                Rc::ptr_eq(&bound.env, &function.env)
+                    && bound.name == function.name
+                    && bound.params == function.params
+                    && bound.body == function.body
+            }
+            _ => false,
+        }
+    }
+
+    fn eval_call_expr(
+        &mut self,
+        func: &Box<ast::Expr>,
+        args: &Vec<ast::Expr>,
+        named: &[(ast::Ident, ast::Expr)],
+    ) -> object::Object {
         let args = args
             .iter()
             .map(|e| self.eval_expr(e).unwrap_or(object::Object::Null))
             .collect::<Vec<_>>();
+        let named = named
+            .iter()
+            .map(|(ident, e)| (ident.clone(), self.eval_expr(e).unwrap_or(object::Object::Null)))
+            .collect::<Vec<_>>();
 
-        let (params, body, env) = match self.eval_expr(&*func) {
-            Some(object::Object::Function(params, body, env)) => (params, body, env),
+        let function = match self.eval_expr(&*func) {
+            Some(object::Object::Function(function)) => function,
+            Some(object::Object::Builtin(_, _)) if !named.is_empty() => {
+                return Self::error(String::from("builtin functions take no named arguments"));
+            }
             Some(object::Object::Builtin(expect_param_num, f)) => {
                 if expect_param_num < 0 || expect_param_num == args.len() as i32 {
                     return f(args);
@@ -414,38 +695,122 @@ impl Evaluator {
                     ));
                 }
             }
-            Some(o) => return Self::error(format!("{} is not valid function", o)),
+            Some(o) => return Self::error(format!("{} is not valid function", o.repr())),
             None => return object::Object::Null,
         };
 
-        if params.len() != args.len() {
-            return Self::error(format!(
-                "wrong number of arguments: {} expected but {} given",
-                params.len(),
-                args.len()
-            ));
-        }
-
+        let object::Function { params, body, env, name } = &*function;
         let current_env = Rc::clone(&self.env);
-        let mut scoped_env = env::Env::new_with_outer(Rc::clone(&env));
-        let list = params.iter().zip(args.iter());
-        for (_, (ident, o)) in list.enumerate() {
-            let ast::Ident(name) = ident.clone();
-            scoped_env.set(name, o.clone());
-        }
+        self.env = Rc::new(RefCell::new(env::Env::new_with_outer(Rc::clone(env))));
 
-        self.env = Rc::new(RefCell::new(scoped_env));
+        if let Some(error) = self.bind_args(params, args, named, name) {
+            self.env = current_env;
+            return error;
+        }
 
-        let object = self.eval_block_stmt(&body);
+        let object = self.eval_block_stmt(body);
 
         self.env = current_env;
 
         match object {
+            // only the innermost named function is mentioned, recursion would repeat it
+            Some(object::Object::Error(msg)) if name.is_some() && !self.error_named => {
+                self.error_named = true;
+                Self::error(format!("{} (in {})", msg, name.clone().unwrap_or_default()))
+            }
             Some(object::Object::ReturnValue(o)) => *o,
             Some(o) => o,
             None => object::Object::Null,
         }
     }
+
+    /// put the args into the current env by position, then by name,
+    /// the params left over take their default, evaluated in order so it can use the params before it
+    fn bind_args(
+        &mut self,
+        params: &[ast::Param],
+        args: Vec<object::Object>,
+        named: Vec<(ast::Ident, object::Object)>,
+        name: &Option<String>,
+    ) -> Option<object::Object> {
+        let to = match name {
+            Some(name) => format!(" to {}", name),
+            None => String::new(),
+        };
+        let given = args.len() + named.len();
+        let required = params.iter().filter(|p| matches!(p, ast::Param::Required(_))).count();
+        let positional = params.iter().filter(|p| !matches!(p, ast::Param::Rest(_))).count();
+        let expected = if positional < params.len() {
+            format!("at least {}", required)
+        } else if required < positional {
+            format!("{} to {}", required, positional)
+        } else {
+            format!("{}", positional)
+        };
+
+        if args.len() > positional && positional == params.len() {
+            return Some(Self::error(format!(
+                "wrong number of arguments{}: {} expected but {} given",
+                to, expected, given
+            )));
+        }
+
+        let mut args = args.into_iter();
+        let mut values = params
+            .iter()
+            .map(|param| match param {
+                ast::Param::Rest(_) => Some(object::Object::Array(args.by_ref().collect())),
+                _ => args.next(),
+            })
+            .collect::<Vec<_>>();
+
+        for (ast::Ident(key), value) in named {
+            let i = match params.iter().position(|param| match param {
+                ast::Param::Required(ident) | ast::Param::Default(ident, _) => ident.0 == key,
+                ast::Param::Rest(_) => false,
+            }) {
+                Some(i) => i,
+                None => return Some(Self::error(format!("unknown argument {}{}", key, to))),
+            };
+            if values[i].is_some() {
+                return Some(Self::error(format!("argument {}{} given twice", key, to)));
+            }
+            values[i] = Some(value);
+        }
+
+        let missing = params
+            .iter()
+            .zip(values.iter())
+            .filter_map(|(param, value)| match (param, value) {
+                (ast::Param::Required(ast::Ident(name)), None) => Some(name.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            return Some(Self::error(format!(
+                "wrong number of arguments{}: {} expected but {} given, missing {}",
+                to,
+                expected,
+                given,
+                missing.join(", ")
+            )));
+        }
+
+        for (param, value) in params.iter().zip(values) {
+            let value = match (param, value) {
+                (_, Some(value)) => value,
+                (ast::Param::Default(_, default), None) => match self.eval_expr(default) {
+                    Some(value) if Self::is_error(&value) => return Some(value),
+                    value => value.unwrap_or(object::Object::Null),
+                },
+                _ => object::Object::Null,
+            };
+            let ast::Ident(name) = param.ident().clone();
+            self.env.borrow_mut().set(name, value);
+        }
+
+        None
+    }
 }
 
 ///
@@ -464,8 +829,10 @@ impl Evaluator {
     fn eval_literal(&mut self, literal: &ast::Literal) -> object::Object {
         match literal {
             ast::Literal::Int(value) => object::Object::Int(*value),
+            ast::Literal::Float(value) => object::Object::Float(*value),
             ast::Literal::String(value) => object::Object::String(value.clone()),
             ast::Literal::Bool(value) => object::Object::Bool(*value),
+            ast::Literal::Null => object::Object::Null,
             ast::Literal::Array(objects) => self.eval_array_literal(objects),
             ast::Literal::Hash(pairs) => self.eval_hash_literal(pairs),
             _ => panic!(),
@@ -492,7 +859,7 @@ impl Evaluator {
                 if let object::Object::Int(i) = index {
                     self.eval_array_index_expr(array.clone(), i)
                 } else {
-                    Self::error(format!("index operator not supported: {}", left))
+                    Self::error(format!("index operator not supported: {}", left.repr()))
                 }
             }
             object::Object::Hash(ref hash) => match index {
@@ -503,9 +870,125 @@ impl Evaluator {
                     }
                 }
                 object::Object::Error(_) => index,
-                _ => Self::error(format!("unusable as hash key: {}", index)),
+                _ => Self::error(format!("unusable as hash key: {}", index.repr())),
+            },
+            _ => Self::error(format!("uknown operator: {} {}", left.repr(), index.repr())),
+        }
+    }
+
+    /// collections are values, so `a.b[0] = v` rebuilds the value bound to `a`
+    /// and updates that binding in whichever env defines it
+    /// the indices are evaluated once, before the value,
+    /// a compound assign reads the old element at them and applies its infix
+    fn eval_index_assign(
+        &mut self,
+        name: &str,
+        target: &ast::Expr,
+        index: &ast::Expr,
+        infix: &Option<ast::Infix>,
+        expr: &ast::Expr,
+    ) -> Option<object::Object> {
+        let mut index_exprs = vec![index];
+        let mut left = target;
+        while let ast::Expr::Index(inner, index, _) = left {
+            index_exprs.push(index);
+            left = inner;
+        }
+
+        let mut indices = vec![];
+        for index_expr in index_exprs.into_iter().rev() {
+            let index = self.eval_expr(index_expr)?;
+            if Self::is_error(&index) {
+                return Some(index);
+            }
+            indices.push(index);
+        }
+
+        let old = match infix {
+            Some(_) => {
+                let mut element = match self.env.borrow_mut().get(name.to_string()) {
+                    Some(current) => current,
+                    None => return Some(Self::error(format!("identifier not found: {}", name))),
+                };
+                for index in &indices {
+                    element = self.eval_index_expr(element, index.clone());
+                    if Self::is_error(&element) {
+                        return Some(element);
+                    }
+                }
+                Some(element)
+            }
+            None => None,
+        };
+
+        let mut value = self.eval_expr(expr)?;
+        if let (Some(infix), Some(old)) = (infix, old) {
+            value = self.eval_infix_expr(infix, old, value);
+        }
+        if Self::is_error(&value) {
+            return Some(value);
+        }
+
+        let current = match self.env.borrow_mut().get(name.to_string()) {
+            Some(current) => current,
+            None => return Some(Self::error(format!("identifier not found: {}", name))),
+        };
+        let updated = Self::assign_index(current, &indices, value);
+        if Self::is_error(&updated) {
+            return Some(updated);
+        }
+
+        let status = self.env.borrow_mut().update(name.to_string(), updated);
+        Self::update_error(status, name)
+    }
+
+    /// the ident an index chain like `a.b[0]` hangs from
+    fn assign_root(target: &ast::Expr) -> Option<String> {
+        match target {
+            ast::Expr::Ident(ast::Ident(name), _) => Some(name.clone()),
+            ast::Expr::Index(left, _, _) => Self::assign_root(left),
+            _ => None,
+        }
+    }
+
+    fn assign_index(
+        container: object::Object,
+        indices: &[object::Object],
+        value: object::Object,
+    ) -> object::Object {
+        let (index, rest) = match indices.split_first() {
+            Some(split) => split,
+            None => return value,
+        };
+
+        match container {
+            object::Object::Array(mut array) => match index {
+                object::Object::Int(i) if *i >= 0 && (*i as usize) < array.len() => {
+                    let i = *i as usize;
+                    let element = std::mem::replace(&mut array[i], object::Object::Null);
+                    let element = Self::assign_index(element, rest, value);
+                    if Self::is_error(&element) {
+                        return element;
+                    }
+                    array[i] = element;
+                    object::Object::Array(array)
+                }
+                object::Object::Int(i) => Self::error(format!("index out of range: {}", i)),
+                _ => Self::error(format!("index operator not supported: {}", index.repr())),
             },
-            _ => Self::error(format!("uknown operator: {} {}", left, index)),
+            object::Object::Hash(mut hash) => match index {
+                object::Object::Int(_) | object::Object::Bool(_) | object::Object::String(_) => {
+                    let element = hash.get(index).cloned().unwrap_or(object::Object::Null);
+                    let element = Self::assign_index(element, rest, value);
+                    if Self::is_error(&element) {
+                        return element;
+                    }
+                    hash.insert(index.clone(), element);
+                    object::Object::Hash(hash)
+                }
+                _ => Self::error(format!("unusable as hash key: {}", index.repr())),
+            },
+            _ => Self::error(format!("index assignment not supported: {}", container.repr())),
         }
     }
 
@@ -523,7 +1006,7 @@ impl Evaluator {
     }
 
     fn eval_hash_literal(&mut self, pairs: &Vec<(ast::Expr, ast::Expr)>) -> object::Object {
-        let mut hash = std::collections::HashMap::new();
+        let mut hash = object::OrderedHash::new();
 
         for (key_expr, value_expr) in pairs {
             let key = self.eval_expr(key_expr).unwrap_or(object::Object::Null);
@@ -556,9 +1039,7 @@ mod tests {
     use std::rc::Rc;
 
     fn eval(input: &str) -> Option<object::Object> {
-        Evaluator {
-            env: Rc::new(RefCell::new(env::Env::from(new_builtins()))),
-        }
+        Evaluator::new(Rc::new(RefCell::new(env::Env::from(new_builtins()))))
         .eval(&Parser::new(Lexer::new(input)).parse())
     }
 
@@ -596,6 +1077,247 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_checked_int_expr() {
+        let tests = vec![
+            (
+                "1 / 0",
+                Some(object::Object::Error(String::from("division by zero: 1 / 0"))),
+            ),
+            (
+                "9223372036854775807 + 1",
+                Some(object::Object::Error(String::from(
+                    "integer overflow: 9223372036854775807 + 1",
+                ))),
+            ),
+            (
+                "-9223372036854775807 - 2",
+                Some(object::Object::Error(String::from(
+                    "integer overflow: -9223372036854775807 - 2",
+                ))),
+            ),
+            (
+                "4611686018427387904 * 2",
+                Some(object::Object::Error(String::from(
+                    "integer overflow: 4611686018427387904 * 2",
+                ))),
+            ),
+            (
+                "-(-9223372036854775807 - 1)",
+                Some(object::Object::Error(String::from(
+                    "integer overflow: --9223372036854775808",
+                ))),
+            ),
+            (
+                "(-9223372036854775807 - 1) / -1",
+                Some(object::Object::Error(String::from(
+                    "integer overflow: -9223372036854775808 / -1",
+                ))),
+            ),
+            ("9223372036854775806 + 1", Some(object::Object::Int(i64::MAX))),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(expect, eval(input));
+        }
+    }
+
+    #[test]
+    fn test_equality_expr() {
+        let tests = vec![
+            ("这是计划的一部分 == 主不在乎", Some(object::Object::Bool(false))),
+            ("主不在乎 == 主不在乎", Some(object::Object::Bool(true))),
+            ("true != false", Some(object::Object::Bool(true))),
+            ("\"a\" == \"a\"", Some(object::Object::Bool(true))),
+            ("\"a\" != \"b\"", Some(object::Object::Bool(true))),
+            ("[1] == [1]", Some(object::Object::Bool(true))),
+            ("[1, [2, \"三\"]] == [1, [2, \"三\"]]", Some(object::Object::Bool(true))),
+            ("[1, 2] == [2, 1]", Some(object::Object::Bool(false))),
+            ("[1] == [1.0]", Some(object::Object::Bool(true))),
+            ("1 == 1.0", Some(object::Object::Bool(true))),
+            ("1 != 1.5", Some(object::Object::Bool(true))),
+            ("9007199254740993 == 9007199254740992.0", Some(object::Object::Bool(false))),
+            ("[9007199254740993] == [9007199254740992.0]", Some(object::Object::Bool(false))),
+            ("0x7fff_ffff_ffff_ffff == 9223372036854775807.0", Some(object::Object::Bool(false))),
+            ("{\"a\": 1, \"b\": 2} == {\"b\": 2, \"a\": 1}", Some(object::Object::Bool(true))),
+            ("{\"a\": 1} == {\"a\": 2}", Some(object::Object::Bool(false))),
+            ("1 == \"1\"", Some(object::Object::Bool(false))),
+            ("1 != \"1\"", Some(object::Object::Bool(true))),
+            ("first([]) == first([])", Some(object::Object::Bool(true))),
+            ("first([]) == 0", Some(object::Object::Bool(false))),
+            ("给 f 以 法则(x) { x }; f == f", Some(object::Object::Bool(true))),
+            ("法则(x) { x } == 法则(y) { y }", Some(object::Object::Bool(false))),
+            (
+                "let a = fn(x) { x }; let b = fn(x) { x }; a == b",
+                Some(object::Object::Bool(false)),
+            ),
+            ("len == len", Some(object::Object::Bool(true))),
+            ("len == first", Some(object::Object::Bool(false))),
+            (
+                "叶文洁 == 1",
+                Some(object::Object::Error(String::from("identifier not found: 叶文洁"))),
+            ),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(expect, eval(input));
+        }
+    }
+
+    #[test]
+    fn test_deep_equal() {
+        let tests = vec![
+            ("没关系的都一样({\"维度\": [1, 2]}, {\"维度\": [1, 2]})", Some(object::Object::Bool(true))),
+            ("没关系的都一样(1, 1.0)", Some(object::Object::Bool(false))),
+            ("没关系的都一样(0.0 / 0.0, 0.0 / 0.0)", Some(object::Object::Bool(true))),
+            ("0.0 / 0.0 == 0.0 / 0.0", Some(object::Object::Bool(false))),
+            (
+                "给 f 以 法则(x) { f(x) }; 没关系的都一样(f, f)",
+                Some(object::Object::Bool(true)),
+            ),
+            (
+                "let a = fn(x) { x }; let b = fn(x) { x }; 没关系的都一样(a, b)",
+                Some(object::Object::Bool(false)),
+            ),
+            (
+                "let a = fn(x) { x }; let b = a; 没关系的都一样([a], [b])",
+                Some(object::Object::Bool(true)),
+            ),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(expect, eval(input));
+        }
+    }
+
+    #[test]
+    fn test_string_ordering_expr() {
+        let tests = vec![
+            ("\"a\" < \"b\"", Some(object::Object::Bool(true))),
+            ("\"abc\" < \"abd\"", Some(object::Object::Bool(true))),
+            ("\"ab\" <= \"ab\"", Some(object::Object::Bool(true))),
+            ("\"b\" > \"abc\"", Some(object::Object::Bool(true))),
+            ("\"\" >= \"a\"", Some(object::Object::Bool(false))),
+            ("\"三体\" > \"三\"", Some(object::Object::Bool(true))),
+            (
+                "\"a\" < 1",
+                Some(object::Object::Error(String::from("type mismatch: \"a\" < 1"))),
+            ),
+            (
+                "\"a\" 前进 1",
+                Some(object::Object::Error(String::from("type mismatch: \"a\" + 1"))),
+            ),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(expect, eval(input));
+        }
+    }
+
+    #[test]
+    fn test_modulo_power_bitwise_expr() {
+        let tests = vec![
+            ("17 % 5", Some(object::Object::Int(2))),
+            ("-17 % 5", Some(object::Object::Int(-2))),
+            ("7.5 % 2", Some(object::Object::Float(1.5))),
+            ("2 ** 10", Some(object::Object::Int(1024))),
+            ("2 ** 3 ** 2", Some(object::Object::Int(512))),
+            ("-2 ** 2", Some(object::Object::Int(-4))),
+            ("2 * 3 ** 2", Some(object::Object::Int(18))),
+            ("2 ** -1", Some(object::Object::Float(0.5))),
+            ("2.0 ** 0.5 > 1.41", Some(object::Object::Bool(true))),
+            ("6 & 3", Some(object::Object::Int(2))),
+            ("6 | 3", Some(object::Object::Int(7))),
+            ("6 ^ 3", Some(object::Object::Int(5))),
+            ("~0", Some(object::Object::Int(-1))),
+            ("1 << 10", Some(object::Object::Int(1024))),
+            ("-16 >> 2", Some(object::Object::Int(-4))),
+            ("1 + 2 << 1", Some(object::Object::Int(6))),
+            ("1 | 2 == 3", Some(object::Object::Bool(true))),
+            (
+                "5 % 0",
+                Some(object::Object::Error(String::from("division by zero: 5 % 0"))),
+            ),
+            (
+                "2 ** 64",
+                Some(object::Object::Error(String::from("integer overflow: 2 ** 64"))),
+            ),
+            (
+                "1 << 64",
+                Some(object::Object::Error(String::from(
+                    "shift amount out of range: 1 << 64",
+                ))),
+            ),
+            (
+                "1 >> -1",
+                Some(object::Object::Error(String::from(
+                    "shift amount out of range: 1 >> -1",
+                ))),
+            ),
+            (
+                "1.5 & 1",
+                Some(object::Object::Error(String::from("unknown operator: 1.5 & 1"))),
+            ),
+            (
+                "~1.5",
+                Some(object::Object::Error(String::from("unknown operator: ~1.5"))),
+            ),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(expect, eval(input));
+        }
+    }
+
+    #[test]
+    fn test_logical_expr() {
+        let tests = vec![
+            ("这是计划的一部分 && 主不在乎", Some(object::Object::Bool(false))),
+            ("true || false", Some(object::Object::Bool(true))),
+            ("1 < 2 并且 2 < 3", Some(object::Object::Bool(true))),
+            ("1 > 2 或者 2 > 3", Some(object::Object::Bool(false))),
+            ("0 && 5", Some(object::Object::Int(5))),
+            ("false || \"罗辑\"", Some(object::Object::String(String::from("罗辑")))),
+            ("false && 叶文洁", Some(object::Object::Bool(false))),
+            ("true || 1 / 0", Some(object::Object::Bool(true))),
+            ("1 == 1 && 2 == 2 || false", Some(object::Object::Bool(true))),
+            (
+                "true && 叶文洁",
+                Some(object::Object::Error(String::from("identifier not found: 叶文洁"))),
+            ),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(expect, eval(input));
+        }
+    }
+
+    #[test]
+    fn test_float_expr() {
+        let tests = vec![
+            ("2.5", Some(object::Object::Float(2.5))),
+            ("-2.5", Some(object::Object::Float(-2.5))),
+            ("+2.5", Some(object::Object::Float(2.5))),
+            ("1.5 + 1.5", Some(object::Object::Float(3.0))),
+            ("1 + 0.5", Some(object::Object::Float(1.5))),
+            ("0.5 * 4", Some(object::Object::Float(2.0))),
+            ("7 / 2.0", Some(object::Object::Float(3.5))),
+            ("1e3 - 1", Some(object::Object::Float(999.0))),
+            ("1 < 1.5", Some(object::Object::Bool(true))),
+            ("2.0 == 2", Some(object::Object::Bool(true))),
+            ("2.5 >= 3", Some(object::Object::Bool(false))),
+            ("1.0 != 1.5", Some(object::Object::Bool(true))),
+            (
+                "1.5 + \"a\"",
+                Some(object::Object::Error(String::from("type mismatch: 1.5 + \"a\""))),
+            ),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(expect, eval(input));
+        }
+    }
+
     #[test]
     fn test_string_expr() {
         let input = "\"Hello World!\"";
@@ -616,6 +1338,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_template_expr() {
+        let tests = vec![
+            ("给 危机纪年 以 3; `危机纪年第${危机纪年}年`", "危机纪年第3年"),
+            ("`${\"三\"}${'体'}`", "三体"),
+            ("`${[1, \"a\", 2.5]} ${{\"k\": 虚空}}`", "[1, \"a\", 2.5] {\"k\": null}"),
+            ("给 f 以 法则(x) { x * 2 }; `${f(2)} ${1 > 2} ${虚空}`", "4 false null"),
+            ("给 a 以 \"外\"; `${`${a}里`}`", "外里"),
+            ("``", ""),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(Some(object::Object::String(String::from(expect))), eval(input), "{}", input);
+        }
+
+        assert_eq!(
+            Some(object::Object::Error(String::from("identifier not found: 不存在"))),
+            eval("`a ${不存在} b`")
+        );
+    }
+
     #[test]
     fn test_boolean_expr() {
         let tests = vec![
@@ -697,7 +1440,7 @@ let two = "two";
 }
 "#;
 
-        let mut hash = std::collections::HashMap::new();
+        let mut hash = object::OrderedHash::new();
         hash.insert(
             object::Object::String(String::from("one")),
             object::Object::Int(1),
@@ -717,6 +1460,16 @@ let two = "two";
         assert_eq!(Some(object::Object::Hash(hash)), eval(input),);
     }
 
+    #[test]
+    fn test_hash_literal_keeps_insertion_order() {
+        let input = r#"{"三体": 3, "地球": 1, 42: true, "三体": 4, false: "主不在乎"}"#;
+
+        assert_eq!(
+            eval(input).map(|obj| obj.to_string()),
+            Some(String::from(r#"{"三体": 4, "地球": 1, 42: true, false: "主不在乎"}"#)),
+        );
+    }
+
     #[test]
     fn test_hash_index_expr() {
         let tests = vec![
@@ -894,18 +1647,21 @@ identity(100);
     fn test_fn_object() {
         let input = "fn(x) { x + 2; };";
 
+        let function = match eval(input) {
+            Some(object::Object::Function(function)) => function,
+            evaluated => panic!("expect function, got {:?}", evaluated),
+        };
+        assert_eq!(function.params, vec![ast::Param::Required(ast::Ident(String::from("x")))]);
         assert_eq!(
-            Some(object::Object::Function(
-                vec![ast::Ident(String::from("x"))],
-                vec![ast::Stmt::Expr(ast::Expr::Infix(
-                    ast::Infix::Plus,
-                    Box::new(ast::Expr::Ident(ast::Ident(String::from("x")))),
-                    Box::new(ast::Expr::Literal(ast::Literal::Int(2))),
-                ))],
-                Rc::new(RefCell::new(env::Env::from(new_builtins()))),
-            )),
-            eval(input),
+            ast::strip_spans(function.body.clone()),
+            vec![ast::Stmt::Expr(ast::Expr::Infix(
+                ast::Infix::Plus,
+                Box::new(ast::Expr::Ident(ast::Ident(String::from("x")), ast::Span::default())),
+                Box::new(ast::Expr::Literal(ast::Literal::Int(2), ast::Span::default())),
+                ast::Span::default(),
+            ), ast::Span::default())],
         );
+        assert_eq!(function.name, None);
     }
 
     #[test]
@@ -957,6 +1713,154 @@ addTwo(2);
         assert_eq!(Some(object::Object::Int(4)), eval(input));
     }
 
+    #[test]
+    fn test_function_stmt() {
+        let tests = vec![
+            (
+                "法则 阶乘(n) { if (n < 2) { 1 } else { n * 阶乘(n - 1) } } 阶乘(5);",
+                Some(object::Object::Int(120)),
+            ),
+            (
+                // called above its declaration, and each calls the other
+                r#"
+给 结果 以 是偶数(10);
+法则 是偶数(n) { if (n == 0) { true } else { 是奇数(n - 1) } }
+法则 是奇数(n) { if (n == 0) { false } else { 是偶数(n - 1) } }
+结果;
+"#,
+                Some(object::Object::Bool(true)),
+            ),
+            (
+                "法则 外(x) { 法则 内() { x * 2 } 内() } 外(21);",
+                Some(object::Object::Int(42)),
+            ),
+            ("法则 名字(x, y) { x }", None),
+            (
+                "let i = 0; 面壁 (i < 3) { 法则 f() { i } i += 1; } i",
+                Some(object::Object::Int(3)),
+            ),
+            (
+                "let i = 0; 面壁 (i < 3) { 法则 f() { i } i += 1; } f()",
+                Some(object::Object::Int(3)),
+            ),
+            (
+                "法则 名字(x) { 1 } 法则 名字(y) { 2 }",
+                Some(object::Object::Error(String::from("Can not redeclare variable 名字!"))),
+            ),
+            (
+                // hoisted, so it is the const which comes second
+                "const 名字 = 1; 法则 名字() { 2 }",
+                Some(object::Object::Error(String::from("Can not redeclare variable 名字!"))),
+            ),
+            (
+                "法则 加(x, y) { x + y } 加(1);",
+                Some(object::Object::Error(String::from(
+                    "wrong number of arguments to 加: 2 expected but 1 given, missing y",
+                ))),
+            ),
+            (
+                "let f = fn(x) { x }; f();",
+                Some(object::Object::Error(String::from(
+                    "wrong number of arguments: 1 expected but 0 given, missing x",
+                ))),
+            ),
+            (
+                "法则 外() { 内() } 法则 内() { 1 + true } 外();",
+                Some(object::Object::Error(String::from(
+                    "type mismatch: 1 + true (in 内)",
+                ))),
+            ),
+            (
+                "法则 倒数(n) { if (n == 0) { 没有 } else { 倒数(n - 1) } } 倒数(3);",
+                Some(object::Object::Error(String::from(
+                    "identifier not found: 没有 (in 倒数)",
+                ))),
+            ),
+            (
+                "法则 f(x) { 回归 x / 0 } f(1);",
+                Some(object::Object::Error(String::from(
+                    "division by zero: 1 / 0 (in f)",
+                ))),
+            ),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(expect, eval(input), "{}", input);
+        }
+
+        assert_eq!(
+            eval("法则 加(x, y) { x + y } 加;").map(|f| f.to_string()),
+            Some(String::from("fn 加(x, y) { ... }"))
+        );
+    }
+
+    #[test]
+    fn test_fn_params() {
+        let tests = vec![
+            ("法则 加(a, b = 1) { a + b } 加(2);", Some(object::Object::Int(3))),
+            ("法则 加(a, b = 1) { a + b } 加(2, 3);", Some(object::Object::Int(5))),
+            ("法则 加(a, b = a * 10) { a + b } 加(2);", Some(object::Object::Int(22))),
+            ("法则 减(a, b) { a - b } 减(b = 1, a = 10);", Some(object::Object::Int(9))),
+            ("法则 减(a = 5, b) { a - b } 减(b = 1);", Some(object::Object::Int(4))),
+            (
+                "法则 收集(first, ...rest) { rest } 收集(1, 2, 3);",
+                Some(object::Object::Array(vec![
+                    object::Object::Int(2),
+                    object::Object::Int(3),
+                ])),
+            ),
+            (
+                "法则 收集(first, ...rest) { rest } 收集(1);",
+                Some(object::Object::Array(vec![])),
+            ),
+            (
+                "法则 收集(a, b = 0, ...rest) { [a, b, len(rest)] } 收集(1, rest = 2);",
+                Some(object::Object::Error(String::from("unknown argument rest to 收集"))),
+            ),
+            (
+                "法则 加(a, b = 1) { a + b } 加(1, 2, 3);",
+                Some(object::Object::Error(String::from(
+                    "wrong number of arguments to 加: 1 to 2 expected but 3 given",
+                ))),
+            ),
+            (
+                "法则 收集(a, b, ...rest) { rest } 收集(b = 1);",
+                Some(object::Object::Error(String::from(
+                    "wrong number of arguments to 收集: at least 2 expected but 1 given, missing a",
+                ))),
+            ),
+            (
+                "fn(a, b, c) { a }();",
+                Some(object::Object::Error(String::from(
+                    "wrong number of arguments: 3 expected but 0 given, missing a, b, c",
+                ))),
+            ),
+            (
+                "法则 加(a, b) { a + b } 加(1, a = 2);",
+                Some(object::Object::Error(String::from("argument a to 加 given twice"))),
+            ),
+            (
+                "法则 加(a, b) { a + b } 加(1, c = 2);",
+                Some(object::Object::Error(String::from("unknown argument c to 加"))),
+            ),
+            (
+                "len(x = [1]);",
+                Some(object::Object::Error(String::from(
+                    "builtin functions take no named arguments",
+                ))),
+            ),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(expect, eval(input), "{}", input);
+        }
+
+        assert_eq!(
+            eval("法则 收集(a, b = 1, ...rest) { a } 收集;").map(|f| f.to_string()),
+            Some(String::from("fn 收集(a, b = ..., ...rest) { ... }"))
+        );
+    }
+
     #[test]
     fn test_builtin_functions() {
         let tests = vec![
@@ -1134,7 +2038,7 @@ addTwo(2);
             (
                 "\"Hello\" - \"World\"",
                 Some(object::Object::Error(String::from(
-                    "unknown operator: Hello - World",
+                    "unknown operator: \"Hello\" - \"World\"",
                 ))),
             ),
             // todo cases
@@ -1145,6 +2049,31 @@ addTwo(2);
         }
     }
 
+    #[test]
+    fn test_error_span() {
+        let input = "给 f 以 法则(x) {\n  x + 主不在乎;\n};\nf(1);";
+        let mut evaluator = Evaluator::new(Rc::new(RefCell::new(env::Env::from(new_builtins()))));
+        let evaluated = evaluator.eval(&Parser::new(Lexer::new(input)).parse());
+
+        assert_eq!(
+            evaluated,
+            Some(object::Object::Error(String::from("type mismatch: 1 + false")))
+        );
+        let span = evaluator.error_span.unwrap();
+        assert_eq!((span.line, span.column), (2, 3));
+
+        let input = "法则 f(x) {\n  回归 x / 0;\n}\nf(1);";
+        let mut evaluator = Evaluator::new(Rc::new(RefCell::new(env::Env::from(new_builtins()))));
+        let evaluated = evaluator.eval(&Parser::new(Lexer::new(input)).parse());
+
+        assert_eq!(
+            evaluated,
+            Some(object::Object::Error(String::from("division by zero: 1 / 0 (in f)")))
+        );
+        let span = evaluator.error_span.unwrap();
+        assert_eq!((span.line, span.column), (2, 3));
+    }
+
     #[test]
     fn test_z_combinator() {
         let input = r#"
@@ -1195,12 +2124,119 @@ return z(fn(f) {
         }
     }
 
+    #[test]
+    fn test_index_assign_evaluator() {
+        let tests = vec![
+            ("let a = [1, 2, 3]; a[1] = 5; a", "[1, 5, 3]"),
+            ("let h = {\"a\": 1}; h.a = 2; h.b = 3; h", "{\"a\": 2, \"b\": 3}"),
+            ("let h = {}; h[true] = 1; h[2] = 2; h", "{true: 1, 2: 2}"),
+            (
+                "给 宇宙 = {\"星系\": [{\"名字\": \"仙女座\"}]}; 宇宙.星系[0].名字 = \"银河\"; 宇宙",
+                "{\"星系\": [{\"名字\": \"银河\"}]}",
+            ),
+            ("let a = [[1, 2], [3, 4]]; a[1][0] = 0; a", "[[1, 2], [0, 4]]"),
+            ("let a = [1]; let b = a; b[0] = 2; a", "[1]"),
+            ("let a = [1]; let f = fn() { a[0] = 2; }; f(); a", "[2]"),
+            ("let a = [1]; let f = fn(a) { a[0] = 2; a }; f(a)[0] + a[0]", "3"),
+            ("let a = [1]; if (true) { a[0] = 7; } a", "[7]"),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(Some(String::from(expect)), eval(input).map(|obj| obj.to_string()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_index_assign_errors() {
+        let tests = vec![
+            ("思想钢印 a = [1]; a[0] = 2;", "Can not assign to constant variable a!"),
+            ("a[0] = 2;", "No identifier a!"),
+            ("let a = [1]; a[1] = 2;", "index out of range: 1"),
+            ("let a = [1]; a[-1] = 2;", "index out of range: -1"),
+            ("let a = [1]; a[\"x\"] = 2;", "index operator not supported: \"x\""),
+            ("let h = {}; h[[1]] = 2;", "unusable as hash key: [1]"),
+            ("let x = 1; x[0] = 2;", "index assignment not supported: 1"),
+            ("let h = {}; h.a.b = 2;", "index assignment not supported: null"),
+            ("let a = [1]; a[0] = b;", "identifier not found: b"),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(Some(object::Object::Error(String::from(expect))), eval(input), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_compound_assign_evaluator() {
+        let tests = vec![
+            ("let x = 5; x += 2; x", "7"),
+            ("let x = 5; x -= 2; x", "3"),
+            ("let x = 5; x *= 2; x", "10"),
+            ("let x = 5; x /= 2; x", "2"),
+            ("let x = 5; x %= 2; x", "1"),
+            ("let x = 5; x += 0.5; x", "5.5"),
+            ("let s = \"三\"; s += \"体\"; s", "\"三体\""),
+            ("给 危机纪年 以 1; 危机纪年 前进以 2; 危机纪年", "3"),
+            ("给 危机纪年 以 1; 危机纪年 降维以 2; 危机纪年", "-1"),
+            ("let a = [1, 2]; a[1] += 3; a", "[1, 5]"),
+            ("let h = {\"n\": 1}; h.n *= 10; h", "{\"n\": 10}"),
+            ("let x = 0; let i = 0; while (i < 4) { i += 1; x += i; } x", "10"),
+            ("let x = 1; let f = fn() { x += 1; }; f(); f(); x", "3"),
+            (
+                "let i = 0; let next = fn() { i += 1; i - 1 }; let a = [0, 0, 0]; a[next()] += 5; [a, i]",
+                "[[5, 0, 0], 1]",
+            ),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(Some(String::from(expect)), eval(input).map(|obj| obj.repr().to_string()), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_compound_assign_errors() {
+        let tests = vec![
+            ("思想钢印 x = 1; x += 1;", "Can not assign to constant variable x!"),
+            ("x += 1;", "No identifier x!"),
+            ("思想钢印 a = [1]; a[0] -= 1;", "Can not assign to constant variable a!"),
+            ("a[0] -= 1;", "No identifier a!"),
+            ("let x = 1; x /= 0;", "division by zero: 1 / 0"),
+            ("let x = 1; x -= true;", "type mismatch: 1 - true"),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(Some(object::Object::Error(String::from(expect))), eval(input), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_chinese_condition_and_null() {
+        let tests = vec![
+            ("如果 (1 < 2) { 10 } 否则 { 20 }", Some(object::Object::Int(10))),
+            ("如果 (1 > 2) { 10 } 否则 { 20 }", Some(object::Object::Int(20))),
+            ("给 f 以 法则(x) { 如果 (x) { 回归 1; } 回归 2; }; f(主不在乎)", Some(object::Object::Int(2))),
+            ("虚空", Some(object::Object::Null)),
+            ("null == 虚空", Some(object::Object::Bool(true))),
+            ("虚空 == 主不在乎", Some(object::Object::Bool(false))),
+            ("如果 (虚空) { 1 } 否则 { 2 }", Some(object::Object::Int(2))),
+            ("[null, 1]", Some(object::Object::Array(vec![object::Object::Null, object::Object::Int(1)]))),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(expect, eval(input), "{}", input);
+        }
+    }
+
     #[test]
     fn test_while_break_continue_evaluator() {
         let tests = vec![
             ("let cond = true; let a = 5; while (cond) { a = 3; cond = false; } a;", Some(object::Object::Int(3))),
             ("let cond = true; let a = 5; while (cond) { a = a - 1; if (a == 2) { break; } } a", Some(object::Object::Int(2))),
             ("let cond = true; let a = 5; while (cond) { a = a - 1; if (a >= 2) { continue; } return a; }", Some(object::Object::Int(1))),
+            (
+                "let i = 0; while (i < 3) { let x = i; i += 1; } i",
+                Some(object::Object::Error(String::from("Can not redeclare variable x!"))),
+            ),
+            ("while (true) { 叶文洁; }", Some(object::Object::Error(String::from("identifier not found: 叶文洁")))),
         ];
 
         for (input, expect) in tests {
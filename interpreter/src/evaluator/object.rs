@@ -2,7 +2,6 @@ use std::fmt;
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
 #[cfg(feature="sophon")]
 use llm;
 
@@ -23,11 +22,13 @@ pub enum NativeObject {
 #[derive(PartialEq, Clone, Debug)]
 pub enum Object {
     Int(i64),
+    Float(f64),
     String(String),
     Bool(bool),
     Array(Vec<Object>),
-    Hash(HashMap<Object, Object>),
-    Function(Vec<ast::Ident>, ast::BlockStmt, Rc<RefCell<env::Env>>),
+    Hash(OrderedHash),
+    /// shared by every copy of the value, which is what makes two functions the same one
+    Function(Rc<Function>),
     Builtin(i32, BuiltinFunc),
     ReturnValue(Box<Object>),
     BreakStatement,
@@ -39,20 +40,145 @@ pub enum Object {
 
 unsafe impl Send for Object {}
 
-/// This is actually repr
+/// A function value: its params and body, the env it closes over,
+/// and its name if it was declared with one
+#[derive(PartialEq, Clone, Debug)]
+pub struct Function {
+    pub params: Vec<ast::Param>,
+    pub body: ast::BlockStmt,
+    pub env: Rc<RefCell<env::Env>>,
+    pub name: Option<String>,
+}
+
+/// The map behind hash values: entries keep their insertion order, so printing and
+/// iterating are deterministic, while the index keeps lookups O(1)
+#[derive(Clone, Debug, Default)]
+pub struct OrderedHash {
+    entries: Vec<(Object, Object)>,
+    index: HashMap<HashKey, usize>,
+}
+
+/// The kinds of value usable as a hash key, they have a real equality (unlike floats)
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+enum HashKey {
+    Int(i64),
+    Bool(bool),
+    String(String),
+}
+
+impl HashKey {
+    fn of(object: &Object) -> Option<HashKey> {
+        match object {
+            Object::Int(i) => Some(HashKey::Int(*i)),
+            Object::Bool(b) => Some(HashKey::Bool(*b)),
+            Object::String(s) => Some(HashKey::String(s.clone())),
+            _ => None,
+        }
+    }
+}
+
+impl OrderedHash {
+    pub fn new() -> Self {
+        OrderedHash::default()
+    }
+
+    /// Inserting an existing key replaces its value but keeps its original position
+    pub fn insert(&mut self, key: Object, value: Object) -> Option<Object> {
+        match self.position(&key) {
+            Some(i) => Some(std::mem::replace(&mut self.entries[i].1, value)),
+            None => {
+                if let Some(hash_key) = HashKey::of(&key) {
+                    self.index.insert(hash_key, self.entries.len());
+                }
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, key: &Object) -> Option<&Object> {
+        self.position(key).map(|i| &self.entries[i].1)
+    }
+
+    pub fn contains_key(&self, key: &Object) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// keys the evaluator rejects never reach here, but they still work, just by a scan
+    fn position(&self, key: &Object) -> Option<usize> {
+        match HashKey::of(key) {
+            Some(hash_key) => self.index.get(&hash_key).copied(),
+            None => self.entries.iter().position(|(k, _)| k == key),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Object, &Object)> {
+        self.entries.iter().map(|(k, v)| (k, v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Object> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Object> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+}
+
+/// Two hashes are equal when they hold the same entries, whatever the order
+impl PartialEq for OrderedHash {
+    fn eq(&self, other: &OrderedHash) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl FromIterator<(Object, Object)> for OrderedHash {
+    fn from_iter<I: IntoIterator<Item = (Object, Object)>>(iter: I) -> Self {
+        let mut hash = OrderedHash::new();
+        for (key, value) in iter {
+            hash.insert(key, value);
+        }
+        hash
+    }
+}
+
+/// The user-facing form, used by 广播 and string conversion:
+/// a string is its own text, every other value reads as its repr
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
+            Object::String(ref value) => write!(f, "{}", value),
+            _ => write!(f, "{}", self.repr()),
+        }
+    }
+}
+
+/// The debug form, used by the repl echo and error messages: strings are quoted and
+/// escaped, and containers show their elements by repr too, so `["1"]` differs from `[1]`
+pub struct Repr<'a>(&'a Object);
+
+impl fmt::Display for Repr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self.0 {
             Object::Int(ref value) => write!(f, "{}", value),
+            Object::Float(ref value) => write!(f, "{:?}", value),
             Object::String(ref value) => write!(f, "{}", escape_str(value)),
             Object::Bool(ref value) => write!(f, "{}", value),
             Object::Array(ref objects) => {
                 let mut result = String::new();
                 for (i, obj) in objects.iter().enumerate() {
                     if i < 1 {
-                        result.push_str(&format!("{}", obj));
+                        result.push_str(&obj.repr().to_string());
                     } else {
-                        result.push_str(&format!(", {}", obj));
+                        result.push_str(&format!(", {}", obj.repr()));
                     }
                 }
                 write!(f, "[{}]", result)
@@ -61,53 +187,112 @@ impl fmt::Display for Object {
                 let mut result = String::new();
                 for (i, (k, v)) in hash.iter().enumerate() {
                     if i < 1 {
-                        result.push_str(&format!("{}: {}", k, v));
+                        result.push_str(&format!("{}: {}", k.repr(), v.repr()));
                     } else {
-                        result.push_str(&format!(", {}: {}", k, v));
+                        result.push_str(&format!(", {}: {}", k.repr(), v.repr()));
                     }
                 }
                 write!(f, "{{{}}}", result)
             }
-            Object::Function(ref params, _, _) => {
+            Object::Function(ref function) => {
                 let mut result = String::new();
-                for (i, ast::Ident(ref s)) in params.iter().enumerate() {
-                    if i < 1 {
-                        result.push_str(&s.to_string());
-                    } else {
-                        result.push_str(&format!(", {}", s));
+                for (i, param) in function.params.iter().enumerate() {
+                    if i > 0 {
+                        result.push_str(", ");
                     }
+                    match param {
+                        ast::Param::Required(ast::Ident(s)) => result.push_str(s),
+                        ast::Param::Default(ast::Ident(s), _) => result.push_str(&format!("{} = ...", s)),
+                        ast::Param::Rest(ast::Ident(s)) => result.push_str(&format!("...{}", s)),
+                    }
+                }
+                match function.name {
+                    Some(ref name) => write!(f, "fn {}({}) {{ ... }}", name, result),
+                    None => write!(f, "fn({}) {{ ... }}", result),
                 }
-                write!(f, "fn({}) {{ ... }}", result)
             }
             Object::Builtin(_, _) => write!(f, "[builtin function]"),
             Object::Null => write!(f, "null"),
             Object::BreakStatement => write!(f, "BreakStatement"),
             Object::ContinueStatement => write!(f, "ContinueStatement"),
-            Object::ReturnValue(ref value) => write!(f, "ReturnValue({})", value),
+            Object::ReturnValue(ref value) => write!(f, "ReturnValue({})", value.repr()),
             Object::Error(ref value) => write!(f, "Error({})", value),
             Object::Native(ref model) => write!(f, "NativeObject({:?})", (model)),
         }
     }
 }
 
-impl Eq for Object {}
+impl Object {
+    /// a new function value, distinct from every other one
+    pub fn function(
+        params: Vec<ast::Param>,
+        body: ast::BlockStmt,
+        env: Rc<RefCell<env::Env>>,
+        name: Option<String>,
+    ) -> Object {
+        Object::Function(Rc::new(Function { params, body, env, name }))
+    }
 
-impl Hash for Object {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        match *self {
-            Object::Int(ref i) => i.hash(state),
-            Object::Bool(ref b) => b.hash(state),
-            Object::String(ref s) => s.hash(state),
-            _ => "".hash(state),
+    pub fn repr(&self) -> Repr<'_> {
+        Repr(self)
+    }
+
+    /// Structural equality as the language sees it (used by `==` and `!=`):
+    /// numbers compare by value across int and float, containers compare element by element,
+    /// and functions compare by identity, so closures never recurse into their env.
+    pub fn structural_eq(&self, other: &Object) -> bool {
+        self.eq_with(other, false)
+    }
+
+    /// Deep equality (used by `没关系的都一样`): like structural_eq, but the kinds must match
+    /// exactly (1 is not 1.0), and NaN equals NaN so that every value is deep equal to itself.
+    /// Values can only reach back to themselves through a closure env, and closures compare
+    /// by identity, so the recursion always terminates.
+    pub fn deep_eq(&self, other: &Object) -> bool {
+        self.eq_with(other, true)
+    }
+
+    fn eq_with(&self, other: &Object, strict: bool) -> bool {
+        match (self, other) {
+            (Object::Int(a), Object::Int(b)) => a == b,
+            (Object::Float(a), Object::Float(b)) => a == b || (strict && a.is_nan() && b.is_nan()),
+            (Object::Int(a), Object::Float(b)) | (Object::Float(b), Object::Int(a)) => {
+                !strict && int_eq_float(*a, *b)
+            }
+            (Object::String(a), Object::String(b)) => a == b,
+            (Object::Bool(a), Object::Bool(b)) => a == b,
+            (Object::Null, Object::Null) => true,
+            (Object::Array(a), Object::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.eq_with(b, strict))
+            }
+            (Object::Hash(a), Object::Hash(b)) => {
+                a.len() == b.len()
+                    && a.iter().all(|(key, value)| match b.get(key) {
+                        Some(other_value) => value.eq_with(other_value, strict),
+                        None => false,
+                    })
+            }
+            (Object::Function(a), Object::Function(b)) => Rc::ptr_eq(a, b),
+            (Object::Builtin(num, func), Object::Builtin(other_num, other_func)) => {
+                num == other_num && std::ptr::fn_addr_eq(*func, *other_func)
+            }
+            (Object::Native(a), Object::Native(b)) => a == b,
+            _ => false,
         }
     }
 }
 
+/// Exact int and float equality, `as f64` would round big ints onto their float neighbours
+fn int_eq_float(int: i64, float: f64) -> bool {
+    // -2^63 and 2^63 are exact floats, every whole float in between fits an i64
+    float.fract() == 0.0 && (-9_223_372_036_854_775_808.0..9_223_372_036_854_775_808.0).contains(&float) && float as i64 == int
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::evaluator::env::Env;
-    use crate::ast::Ident;
+    use crate::ast::{Ident, Param};
 
     #[test]
     fn test_object_int() {
@@ -115,10 +300,22 @@ mod tests {
         assert_eq!(obj.to_string(), "520");
     }
 
+    #[test]
+    fn test_object_float() {
+        assert_eq!(Object::Float(3.0).to_string(), "3.0");
+        assert_eq!(Object::Float(0.1).to_string(), "0.1");
+        assert_eq!(Object::Float(6.67e-11).to_string(), "6.67e-11");
+    }
+
     #[test]
     fn test_object_string() {
         let obj = Object::String("woshiaf".to_string());
-        assert_eq!(obj.to_string(), "\"woshiaf\"");
+        assert_eq!(obj.to_string(), "woshiaf");
+        assert_eq!(obj.repr().to_string(), "\"woshiaf\"");
+
+        let obj = Object::String("三\n体".to_string());
+        assert_eq!(obj.to_string(), "三\n体");
+        assert_eq!(obj.repr().to_string(), "\"三\\n体\"");
     }
 
     #[test]
@@ -135,21 +332,62 @@ mod tests {
 
     #[test]
     fn test_object_hash() {
-        let mut hash = HashMap::new();
+        let mut hash = OrderedHash::new();
         hash.insert(Object::String("a".to_string()), Object::Int(1));
 
         let obj = Object::Hash(hash);
         assert_eq!(obj.to_string(), "{\"a\": 1}");
     }
 
+    #[test]
+    fn test_object_nested_repr() {
+        let mut hash = OrderedHash::new();
+        hash.insert(Object::String("k".to_string()), Object::String("v\t".to_string()));
+        let obj = Object::Array(vec![
+            Object::String("1".to_string()),
+            Object::Int(1),
+            Object::Hash(hash),
+        ]);
+
+        assert_eq!(obj.to_string(), "[\"1\", 1, {\"k\": \"v\\t\"}]");
+        assert_eq!(obj.repr().to_string(), obj.to_string());
+        assert_eq!(
+            Object::ReturnValue(Box::new(Object::String("a".to_string()))).to_string(),
+            "ReturnValue(\"a\")"
+        );
+    }
+
+    #[test]
+    fn test_object_hash_keeps_insertion_order() {
+        let mut hash = OrderedHash::new();
+        for key in ["z", "a", "m", "b"] {
+            hash.insert(Object::String(key.to_string()), Object::Int(1));
+        }
+        hash.insert(Object::String("a".to_string()), Object::Int(2));
+
+        assert_eq!(
+            Object::Hash(hash).to_string(),
+            "{\"z\": 1, \"a\": 2, \"m\": 1, \"b\": 1}"
+        );
+    }
+
     #[test]
     fn test_object_func() {
-        let obj = Object::Function(
-            vec![Ident("x".to_string()), Ident("y".to_string())],
+        let obj = Object::function(
+            vec![Param::Required(Ident("x".to_string())), Param::Required(Ident("y".to_string()))],
             vec![],
             Rc::new(RefCell::new(Env::new())),
+            None,
         );
         assert_eq!(format!("{}", obj), "fn(x, y) { ... }");
+
+        let obj = Object::function(
+            vec![Param::Required(Ident("x".to_string()))],
+            vec![],
+            Rc::new(RefCell::new(Env::new())),
+            Some("阶乘".to_string()),
+        );
+        assert_eq!(format!("{}", obj), "fn 阶乘(x) { ... }");
     }
 
     #[test]
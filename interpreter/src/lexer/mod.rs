@@ -1,13 +1,41 @@
 extern crate unicode_xid;
-use crate::token::Token;
+use crate::token::keyword::KeywordSet;
+use crate::token::{LexError, LosslessToken, Span, SpannedToken, TemplateSegment, Token};
+pub mod numeral;
 pub mod unescape;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Lexer {
     input: Vec<char>,
     pos: usize,
     next_pos: usize,
-    ch: char
+    ch: char,
+    // byte offset of every char (plus the end of input)
+    offsets: Vec<usize>,
+    // char index where every line starts
+    line_starts: Vec<usize>,
+    token_start: usize,
+    keywords: KeywordSet,
+    // where the input starts in the whole source, for code embedded in template strings
+    origin: Span,
+    // reject full-width punctuation and digits instead of reading them as ascii
+    strict: bool,
+}
+
+/// The ascii char a full-width punctuation or digit stands for, as typed by a chinese IME.
+/// Letters and quotes are left alone, so identifiers and strings keep their own text.
+pub fn half_width(c: char) -> Option<char> {
+    match c {
+        '\u{3000}' => Some(' '),
+        '【' => Some('['),
+        '】' => Some(']'),
+        '＂' | '＇' | '＼' | '｀' => None,
+        '\u{ff01}'..='\u{ff5e}' => {
+            let ascii = char::from_u32(c as u32 - 0xfee0)?;
+            Some(ascii).filter(|ascii| !ascii.is_ascii_alphabetic())
+        }
+        _ => None,
+    }
 }
 
 
@@ -34,12 +62,36 @@ fn is_id_continue(c: char) -> bool {
 impl Lexer {
 
     pub fn new(origin_input: &str) -> Self {
+        Self::with_keywords(origin_input, KeywordSet::default())
+    }
+
+    /// a lexer reading keywords from a locale pack
+    pub fn with_keywords(origin_input: &str, keywords: KeywordSet) -> Self {
         let input = origin_input.chars().collect::<Vec<char>>();
+
+        let mut offsets = Vec::with_capacity(input.len() + 1);
+        let mut line_starts = vec![0];
+        let mut offset = 0;
+        for (i, c) in input.iter().enumerate() {
+            offsets.push(offset);
+            offset += c.len_utf8();
+            if *c == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        offsets.push(offset);
+
         let mut lexer = Self {
             input,
             pos: 0,
             next_pos: 0,
             ch: '\0',
+            offsets,
+            line_starts,
+            token_start: 0,
+            keywords,
+            origin: Span::new(0, 0, 1, 1),
+            strict: false,
         };
 
         lexer.walk_char();
@@ -47,36 +99,163 @@ impl Lexer {
         lexer
     }
 
+    /// strict mode keeps full-width punctuation as it is and reports it as an error
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+        self.ch = self.char_at(self.pos);
+    }
+
     fn walk_char(&mut self) {
-        if self.next_pos >= self.input.len() {
-            self.ch = '\0';
-        } else {
-            self.ch = self.input[self.next_pos];
-        }
+        self.ch = self.char_at(self.next_pos);
         self.pos = self.next_pos;
         self.next_pos += 1;
     }
 
+    /// the char at pos as the lexer reads it, full-width punctuation becomes ascii
+    fn char_at(&self, pos: usize) -> char {
+        match self.input.get(pos) {
+            Some(&c) if self.strict => c,
+            Some(&c) => half_width(c).unwrap_or(c),
+            None => '\0',
+        }
+    }
+
+    /// the text between two char indexes as the lexer reads it
+    fn text(&self, start: usize, end: usize) -> String {
+        (start..end).map(|pos| self.char_at(pos)).collect()
+    }
+
+    /// next token together with its span in the source
+    pub fn next_spanned_token(&mut self) -> SpannedToken {
+        let token = self.next_token();
+        let end = std::cmp::min(self.pos, self.input.len());
+        SpannedToken {
+            token,
+            span: self.span(self.token_start, end),
+        }
+    }
+
+    /// read the input as if it started at origin of a longer source,
+    /// like a repl line after the lines entered before it
+    pub fn set_origin(&mut self, origin: Span) {
+        self.origin = origin;
+    }
+
+    /// a lexer for code embedded at origin, sharing the keywords of self
+    pub fn nested(&self, input: &str, origin: Span) -> Self {
+        let mut lexer = Self::with_keywords(input, self.keywords.clone());
+        lexer.origin = origin;
+        lexer.set_strict(self.strict);
+        lexer
+    }
+
+    /// all the remaining tokens up to and including Eof, keeping trivia and source text
+    pub fn lossless_tokens(&mut self) -> Vec<LosslessToken> {
+        let mut tokens = vec![];
+        let mut last_end = std::cmp::min(self.pos, self.input.len());
+
+        loop {
+            let token = self.next_token();
+            let end = std::cmp::min(self.pos, self.input.len());
+            let is_eof = token == Token::Eof;
+            tokens.push(LosslessToken {
+                trivia: self.input[last_end..self.token_start].iter().collect(),
+                token,
+                text: self.input[self.token_start..end].iter().collect(),
+            });
+            last_end = end;
+
+            if is_eof {
+                return tokens;
+            }
+        }
+    }
+
+    /// the span between two char indexes
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        let line = self.line_starts.partition_point(|&line_start| line_start <= start);
+        let mut column = start - self.line_starts[line - 1] + 1;
+        if line == 1 {
+            column += self.origin.column - 1;
+        }
+        Span::new(
+            self.origin.start + self.offsets[start],
+            self.origin.start + self.offsets[end],
+            self.origin.line + line - 1,
+            column,
+        )
+    }
+
     pub fn next_token(&mut self) -> Token {
         self.skip_whitespace();
+        self.token_start = std::cmp::min(self.pos, self.input.len());
 
         let tok = match self.ch {
-            '+' => Token::Plus,
-            '-' => Token::Minus,
+            '+' => {
+                if self.next_is('=') {
+                    self.walk_char();
+                    Token::PlusAssign
+                } else {
+                    Token::Plus
+                }
+            },
+            '-' => {
+                if self.next_is('=') {
+                    self.walk_char();
+                    Token::MinusAssign
+                } else {
+                    Token::Minus
+                }
+            },
             '/' => {
                 if self.next_is('/') {
                     self.walk_char();
+                    if self.next_is('/') && self.peek_ch(2) != '/' {
+                        return self.consume_doc_comment();
+                    }
                     self.skip_comment();
                     return self.next_token();
+                } else if self.next_is('*') {
+                    self.walk_char();
+                    if !self.skip_block_comment() {
+                        return Token::Error(LexError::UnterminatedComment);
+                    }
+                    return self.next_token();
+                } else if self.next_is('=') {
+                    self.walk_char();
+                    Token::SlashAssign
                 } else {
                     Token::Slash
                 }
             },
-            '*' => Token::Asterisk,
+            '*' => {
+                if self.next_is('*') {
+                    self.walk_char();
+                    Token::Power
+                } else if self.next_is('=') {
+                    self.walk_char();
+                    Token::AsteriskAssign
+                } else {
+                    Token::Asterisk
+                }
+            },
+            '%' => {
+                if self.next_is('=') {
+                    self.walk_char();
+                    Token::PercentAssign
+                } else {
+                    Token::Percent
+                }
+            },
+            '^' => Token::BitXor,
+            '~' => Token::Tilde,
             '<' => {
                 if self.next_is('=') {
                     self.walk_char();
                     Token::LTEQ
+                } else if self.next_is('<') {
+                    self.walk_char();
+                    Token::ShiftLeft
                 } else {
                     Token::LT
                 }
@@ -85,14 +264,33 @@ impl Lexer {
                 if self.next_is('=') {
                     self.walk_char();
                     Token::GTEQ
+                } else if self.next_is('>') {
+                    self.walk_char();
+                    Token::ShiftRight
                 } else {
                     Token::GT
                 }
             },
-            ';' | '；' => Token::Semicolon,
-            ',' | '，' => Token::Comma,
-            '(' | '（' => Token::LParen,
-            ')' | '）' => Token::RParen,
+            '&' => {
+                if self.next_is('&') {
+                    self.walk_char();
+                    Token::And
+                } else {
+                    Token::BitAnd
+                }
+            },
+            '|' => {
+                if self.next_is('|') {
+                    self.walk_char();
+                    Token::Or
+                } else {
+                    Token::BitOr
+                }
+            },
+            ';' => Token::Semicolon,
+            ',' => Token::Comma,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
             '{' => Token::LBrace,
             '}' => Token::RBrace,
             '=' => {
@@ -113,14 +311,25 @@ impl Lexer {
             },
             '\0' => Token::Eof,
             '"' => {
-                return self.consume_string();
+                return self.consume_string('"');
             },
             '\'' => {
-                return self.consume_single_quotation_marks_string();
+                return self.consume_string('\'');
+            },
+            '`' => {
+                return self.consume_template();
             },
             '[' => Token::LBracket,
             ']' => Token::RBracket,
-            '.' => Token::Dot,
+            '.' => {
+                if self.next_is('.') && self.peek_ch(2) == '.' {
+                    self.walk_char();
+                    self.walk_char();
+                    Token::Ellipsis
+                } else {
+                    Token::Dot
+                }
+            },
             ':' => Token::Colon,
             '0'..='9' => {
                 return self.consume_number()
@@ -136,6 +345,8 @@ impl Lexer {
             _ => {
                 if is_id_start(self.ch) {
                     return self.consume_identifier();
+                } else if half_width(self.ch).is_some() {
+                    Token::Error(LexError::FullWidth(self.ch))
                 } else {
                     Token::Illegal
                 }
@@ -151,11 +362,12 @@ impl Lexer {
     }
 
     fn next_ch(&mut self) -> char {
-        if self.next_pos >= self.input.len() {
-            '\0'
-        } else {
-            self.input[self.next_pos]
-        }
+        self.char_at(self.next_pos)
+    }
+
+    /// the char n places after the current one
+    fn peek_ch(&self, n: usize) -> char {
+        self.char_at(self.pos + n)
     }
 
     fn skip_whitespace(&mut self) {
@@ -196,6 +408,43 @@ impl Lexer {
         self.walk_char();
     }
 
+    /// walk past a /* ... */ comment, which may nest, false if the input ends first
+    fn skip_block_comment(&mut self) -> bool {
+        let mut depth = 1;
+        self.walk_char();
+
+        while self.pos < self.input.len() {
+            if self.ch == '/' && self.next_is('*') {
+                depth += 1;
+                self.walk_char();
+            } else if self.ch == '*' && self.next_is('/') {
+                depth -= 1;
+                self.walk_char();
+                if depth == 0 {
+                    self.walk_char();
+                    return true;
+                }
+            }
+            self.walk_char();
+        }
+
+        false
+    }
+
+    /// `/// text` up to the end of the line, kept as a token so the parser can attach it
+    fn consume_doc_comment(&mut self) -> Token {
+        self.walk_char();
+        self.walk_char();
+        let start = self.pos;
+        while self.pos < self.input.len() && self.ch != '\n' {
+            self.walk_char();
+        }
+
+        let text = self.input[start..self.pos].iter().collect::<String>();
+        let text = text.strip_prefix(' ').unwrap_or(&text).trim_end_matches('\r');
+        Token::DocComment(text.to_string())
+    }
+
     fn consume_identifier(&mut self) -> Token {
         let start_pos = self.pos;
 
@@ -209,94 +458,205 @@ impl Lexer {
 
         let end_pos = self.pos;
 
-        let literal = self.input[start_pos..end_pos].iter().collect::<String>();
-
-        match literal.as_str() {
-            "let" => Token::Let,
-            "给" => Token::Let,
-            "以" => Token::Assign,
-            "前进" => Token::Plus,
-            "降维" => Token::Minus,
-            "const" => Token::Const,
-            "思想钢印" => Token::Const,
-            "fn" => Token::Function,
-            "法则" => Token::Function,
-            "true" => Token::Bool(true),
-            "false" => Token::Bool(false),
-            "这是计划的一部分" => Token::Bool(true),
-            "主不在乎" => Token::Bool(false),
-            "if" => Token::If,
-            "while" => Token::While,
-            "else" => Token::Else,
-            "break" => Token::Break,
-            "continue" => Token::Continue,
-            "return" => Token::Return,
-            "面壁" => Token::While,
-            "破壁" => Token::Break,
-            "延绪" => Token::Continue,
-            _ => {
-                Token::Ident(literal)
-            }
+        let literal = self.text(start_pos, end_pos);
+
+        match self.keywords.lookup(&literal) {
+            Some(token) => token,
+            None if literal.chars().all(numeral::is_numeral_char) => match numeral::parse_numeral(&literal) {
+                Ok(value) => Token::Int(value),
+                Err(error) => Token::Error(error),
+            },
+            None => Token::Ident(literal),
         }
     }
 
     fn consume_number(&mut self) -> Token {
         let start_pos = self.pos;
+        let mut is_float = false;
 
-        loop {
-            match self.ch {
-                '0'..='9' => {
+        if self.ch == '0' {
+            match self.next_ch() {
+                'x' => return self.consume_radix_number(16),
+                'o' => return self.consume_radix_number(8),
+                'b' => return self.consume_radix_number(2),
+                _ => {}
+            }
+        }
+
+        self.consume_digits();
+
+        // fraction part, only when a digit follows the dot
+        if self.ch == '.' && self.next_ch().is_ascii_digit() {
+            is_float = true;
+            self.walk_char();
+            self.consume_digits();
+        }
+
+        // exponent part, e.g. 6.67e-11
+        if matches!(self.ch, 'e' | 'E') {
+            let sign = matches!(self.next_ch(), '+' | '-');
+            let digit_pos = if sign { self.next_pos + 1 } else { self.next_pos };
+            if self.char_at(digit_pos).is_ascii_digit() {
+                is_float = true;
+                self.walk_char();
+                if sign {
                     self.walk_char();
                 }
-                _ => {
-                    break;
-                }
+                self.consume_digits();
             }
         }
 
         let end_pos = self.pos;
 
-        let literal = &self.input[start_pos..end_pos].iter().collect::<String>();
-        Token::Int(literal.parse::<i64>().unwrap())
+        let literal = &self.text(start_pos, end_pos);
+        // a `_` only separates digits, e.g. 1_000_000
+        let trailing_underscore = literal
+            .char_indices()
+            .any(|(i, c)| c == '_' && !literal[i + 1..].starts_with(|next: char| next.is_ascii_digit() || next == '_'));
+        if trailing_underscore {
+            return Token::Error(LexError::TrailingUnderscore(literal.clone()));
+        }
+
+        let digits = literal.replace('_', "");
+        if is_float {
+            Token::Float(digits.parse::<f64>().unwrap())
+        } else {
+            match digits.parse::<i64>() {
+                Ok(value) => Token::Int(value),
+                Err(_) => Token::Error(LexError::IntegerOutOfRange(literal.clone())),
+            }
+        }
     }
 
-    fn consume_string(&mut self) -> Token {
+    fn consume_digits(&mut self) {
+        while self.ch.is_ascii_digit() || self.ch == '_' {
+            self.walk_char();
+        }
+    }
+
+    /// 0x1F, 0o17 or 0b1010, the whole alphanumeric run is read so a bad digit is reported
+    /// together with its literal instead of starting an identifier
+    fn consume_radix_number(&mut self, radix: u32) -> Token {
+        let start_pos = self.pos;
+        self.walk_char();
+        self.walk_char();
+        while self.ch.is_ascii_alphanumeric() || self.ch == '_' {
+            self.walk_char();
+        }
+
+        let literal = self.text(start_pos, self.pos);
+        let digits = &literal[2..];
+        if digits.chars().all(|c| c == '_') {
+            return Token::Error(LexError::MissingDigits(literal));
+        }
+        if let Some(digit) = digits.chars().find(|&c| c != '_' && !c.is_digit(radix)) {
+            return Token::Error(LexError::InvalidDigit(literal, digit));
+        }
+        if digits.ends_with('_') {
+            return Token::Error(LexError::TrailingUnderscore(literal));
+        }
+
+        match i64::from_str_radix(&digits.replace('_', ""), radix) {
+            Ok(value) => Token::Int(value),
+            Err(_) => Token::Error(LexError::IntegerOutOfRange(literal)),
+        }
+    }
+
+    /// a string in either quote style, running into the end of input is an error
+    fn consume_string(&mut self, quote: char) -> Token {
         self.walk_char();
         let start_pos = self.pos;
 
         loop {
-            if self.ch == '"' {
+            if self.pos >= self.input.len() {
+                return Token::Error(LexError::UnterminatedString(quote));
+            } else if self.ch == '\\' {
+                // the escaped char never closes the string, unescape checks it
+                self.walk_char();
+                self.walk_char();
+            } else if self.ch == quote {
                 let end_pos = self.pos;
-                let literal = self.input[start_pos..end_pos].iter().collect::<String>();
+                let raw = self.input[start_pos..end_pos].iter().collect::<String>();
                 self.walk_char();
-                return Token::String(literal);
+                return match unescape::unescape(&raw) {
+                    Ok(literal) => Token::String(literal),
+                    Err(error) => Token::Error(error),
+                };
             } else {
                 self.walk_char();
             }
         }
-        // infinity loop bug when let a = "3
     }
 
-    fn consume_single_quotation_marks_string(&mut self) -> Token {
+    /// `text ${expr} text`, the code between ${ and its matching } is kept as source
+    fn consume_template(&mut self) -> Token {
         self.walk_char();
-        let start_pos = self.pos;
+        let mut segments = vec![];
+        let mut text_start = self.pos;
 
         loop {
-            if self.ch == '\'' {
-                let end_pos = self.pos;
-                let literal = self.input[start_pos..end_pos].iter().collect::<String>();
+            if self.pos >= self.input.len() {
+                return Token::Error(LexError::UnterminatedString('`'));
+            } else if self.ch == '\\' {
+                self.walk_char();
                 self.walk_char();
-                return Token::String(literal);
+            } else if self.ch == '`' || (self.ch == '$' && self.input.get(self.next_pos) == Some(&'{')) {
+                let raw = self.input[text_start..self.pos].iter().collect::<String>();
+                match unescape::unescape(&raw) {
+                    Ok(text) if text.is_empty() => {}
+                    Ok(text) => segments.push(TemplateSegment::Text(text)),
+                    Err(error) => return Token::Error(error),
+                }
+
+                if self.ch == '`' {
+                    self.walk_char();
+                    return Token::Template(segments);
+                }
+
+                self.walk_char();
+                self.walk_char();
+                let code_start = self.pos;
+                if !self.skip_template_code() {
+                    return Token::Error(LexError::UnterminatedString('`'));
+                }
+                let code = self.input[code_start..self.pos].iter().collect::<String>();
+                segments.push(TemplateSegment::Code(code, self.span(code_start, self.pos)));
+                self.walk_char();
+                text_start = self.pos;
             } else {
                 self.walk_char();
             }
         }
     }
+
+    /// walk to the } closing an embedded expression, false if the input ends first
+    fn skip_template_code(&mut self) -> bool {
+        let mut depth = 0;
+        let mut quote = None;
+
+        while self.pos < self.input.len() {
+            match (quote, self.ch) {
+                (Some(_), '\\') => self.walk_char(),
+                (Some(q), ch) if ch == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'' | '`') => quote = Some(self.ch),
+                (None, '{') => depth += 1,
+                (None, '}') if depth == 0 => return true,
+                (None, '}') => depth -= 1,
+                (None, _) => {}
+            }
+            self.walk_char();
+        }
+
+        false
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::token::Token;
+    use crate::lexer::unescape::escape_str;
+    use crate::token::keyword::KeywordSet;
+    use crate::token::{LexError, Span, TemplateSegment, Token};
 
     use super::Lexer;
     // use crate::Token;
@@ -320,6 +680,541 @@ mod tests {
         assert_eq!(lexer.next_token(), Token::Eof);
     }
 
+    #[test]
+    fn test_ellipsis_token() {
+        let mut lexer = Lexer::new("...rest .. a.b 1...");
+
+        let tests = vec![
+            Token::Ellipsis,
+            Token::Ident(String::from("rest")),
+            Token::Dot,
+            Token::Dot,
+            Token::Ident(String::from("a")),
+            Token::Dot,
+            Token::Ident(String::from("b")),
+            Token::Int(1),
+            Token::Ellipsis,
+            Token::Eof,
+        ];
+
+        for expect in tests {
+            assert_eq!(lexer.next_token(), expect);
+        }
+    }
+
+    #[test]
+    fn test_float_token() {
+        let mut lexer = Lexer::new("2.5 0.5 6.67e-11 1e3 2E+2 7 1.e 3.x");
+
+        let tests = vec![
+            Token::Float(2.5),
+            Token::Float(0.5),
+            Token::Float(6.67e-11),
+            Token::Float(1000.0),
+            Token::Float(200.0),
+            Token::Int(7),
+            Token::Int(1),
+            Token::Dot,
+            Token::Ident(String::from("e")),
+            Token::Int(3),
+            Token::Dot,
+            Token::Ident(String::from("x")),
+            Token::Eof,
+        ];
+
+        for expect in tests {
+            assert_eq!(lexer.next_token(), expect);
+        }
+    }
+
+    #[test]
+    fn test_integer_out_of_range() {
+        let mut lexer = Lexer::new("9223372036854775807 9223372036854775808;");
+        assert_eq!(lexer.next_token(), Token::Int(i64::MAX));
+        assert_eq!(
+            lexer.next_token(),
+            Token::Error(LexError::IntegerOutOfRange(String::from("9223372036854775808")))
+        );
+        assert_eq!(lexer.next_token(), Token::Semicolon);
+    }
+
+    #[test]
+    fn test_arithmetic_bitwise_token() {
+        let mut lexer = Lexer::new("a % b ** c & d | e ^ f << g >> h ~i <= >=");
+
+        let tests = vec![
+            Token::Ident(String::from("a")),
+            Token::Percent,
+            Token::Ident(String::from("b")),
+            Token::Power,
+            Token::Ident(String::from("c")),
+            Token::BitAnd,
+            Token::Ident(String::from("d")),
+            Token::BitOr,
+            Token::Ident(String::from("e")),
+            Token::BitXor,
+            Token::Ident(String::from("f")),
+            Token::ShiftLeft,
+            Token::Ident(String::from("g")),
+            Token::ShiftRight,
+            Token::Ident(String::from("h")),
+            Token::Tilde,
+            Token::Ident(String::from("i")),
+            Token::LTEQ,
+            Token::GTEQ,
+            Token::Eof,
+        ];
+
+        for expect in tests {
+            assert_eq!(lexer.next_token(), expect);
+        }
+    }
+
+    #[test]
+    fn test_compound_assign_token() {
+        let mut lexer = Lexer::new("a += 1; a -= 1; a *= 1; a /= 1; a %= 1; a **= 1; 纪年 前进以 1; 纪年 降维以 1");
+
+        let tests = vec![
+            Token::Ident(String::from("a")),
+            Token::PlusAssign,
+            Token::Int(1),
+            Token::Semicolon,
+            Token::Ident(String::from("a")),
+            Token::MinusAssign,
+            Token::Int(1),
+            Token::Semicolon,
+            Token::Ident(String::from("a")),
+            Token::AsteriskAssign,
+            Token::Int(1),
+            Token::Semicolon,
+            Token::Ident(String::from("a")),
+            Token::SlashAssign,
+            Token::Int(1),
+            Token::Semicolon,
+            Token::Ident(String::from("a")),
+            Token::PercentAssign,
+            Token::Int(1),
+            Token::Semicolon,
+            Token::Ident(String::from("a")),
+            Token::Power,
+            Token::Assign,
+            Token::Int(1),
+            Token::Semicolon,
+            Token::Ident(String::from("纪年")),
+            Token::PlusAssign,
+            Token::Int(1),
+            Token::Semicolon,
+            Token::Ident(String::from("纪年")),
+            Token::MinusAssign,
+            Token::Int(1),
+            Token::Eof,
+        ];
+
+        for expect in tests {
+            assert_eq!(lexer.next_token(), expect);
+        }
+    }
+
+    #[test]
+    fn test_chinese_keyword_token() {
+        let mut lexer = Lexer::new("如果 (甲) { 回归 虚空; } 否则 { return null; }");
+
+        let tests = vec![
+            Token::If,
+            Token::LParen,
+            Token::Ident(String::from("甲")),
+            Token::RParen,
+            Token::LBrace,
+            Token::Return,
+            Token::Null,
+            Token::Semicolon,
+            Token::RBrace,
+            Token::Else,
+            Token::LBrace,
+            Token::Return,
+            Token::Null,
+            Token::Semicolon,
+            Token::RBrace,
+            Token::Eof,
+        ];
+
+        for expect in tests {
+            assert_eq!(lexer.next_token(), expect);
+        }
+    }
+
+    #[test]
+    fn test_keyword_set_token() {
+        let input = "給 文明 以 5; 思想钢印 水 = 1;";
+
+        let mut lexer = Lexer::with_keywords(input, KeywordSet::traditional());
+        let tests = vec![
+            Token::Let,
+            Token::Ident(String::from("文明")),
+            Token::Assign,
+            Token::Int(5),
+            Token::Semicolon,
+            Token::Ident(String::from("思想钢印")),
+            Token::Ident(String::from("水")),
+            Token::Assign,
+            Token::Int(1),
+            Token::Semicolon,
+            Token::Eof,
+        ];
+        for expect in tests {
+            assert_eq!(lexer.next_token(), expect);
+        }
+
+        let mut lexer = Lexer::with_keywords(input, KeywordSet::english());
+        assert_eq!(lexer.next_token(), Token::Ident(String::from("給")));
+        assert_eq!(lexer.next_token(), Token::Ident(String::from("文明")));
+        assert_eq!(lexer.next_token(), Token::Ident(String::from("以")));
+    }
+
+    #[test]
+    fn test_lossless_tokens() {
+        let input = "给 a 以 \"s\" ; // note\n\n  a+1 ";
+        let tokens = Lexer::new(input).lossless_tokens();
+
+        let joined: String = tokens
+            .iter()
+            .map(|token| format!("{}{}", token.trivia, token.text))
+            .collect();
+        assert_eq!(joined, input);
+        assert_eq!(tokens.last().map(|token| token.token.clone()), Some(Token::Eof));
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let tests = vec![
+            ("\"3", '"'),
+            ("'三体", '\''),
+            ("\"", '"'),
+            ("\"a'", '"'),
+        ];
+
+        for (input, quote) in tests {
+            let mut lexer = Lexer::new(input);
+            let spanned = lexer.next_spanned_token();
+            assert_eq!(spanned.token, Token::Error(LexError::UnterminatedString(quote)));
+            assert_eq!((spanned.span.start, spanned.span.end), (0, input.len()));
+            assert_eq!(lexer.next_token(), Token::Eof);
+        }
+    }
+
+    #[test]
+    fn test_string_escape() {
+        let tests = vec![
+            (r#""a\nb""#, Token::String(String::from("a\nb"))),
+            (r#"'a\tb'"#, Token::String(String::from("a\tb"))),
+            (r#""say \"hi\"""#, Token::String(String::from("say \"hi\""))),
+            (r#"'it\'s'"#, Token::String(String::from("it's"))),
+            (r#""\u{4E09}\u{4F53}""#, Token::String(String::from("三体"))),
+            (r#""\\""#, Token::String(String::from("\\"))),
+            (r#""\q" 1"#, Token::Error(LexError::InvalidEscape(String::from(r#"\q"#)))),
+            (r#"'\u{zz}' 1"#, Token::Error(LexError::InvalidUnicodeEscape(String::from(r#"\u{z"#)))),
+            (r#""\""#, Token::Error(LexError::UnterminatedString('"'))),
+        ];
+
+        for (input, expect) in tests {
+            let mut lexer = Lexer::new(input);
+            assert_eq!(lexer.next_token(), expect, "{}", input);
+            if input.ends_with(" 1") {
+                assert_eq!(lexer.next_token(), Token::Int(1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_string_escape_round_trip() {
+        for s in ["三体", "line\nbreak", "tab\t\"quote\"", "back\\slash", "\0\r", "e\u{301}", "\u{200b}", "it's"] {
+            let mut lexer = Lexer::new(&escape_str(s));
+            assert_eq!(lexer.next_token(), Token::String(String::from(s)));
+            assert_eq!(lexer.next_token(), Token::Eof);
+        }
+    }
+
+    #[test]
+    fn test_template() {
+        let input = "`纪年 ${危机纪年 + 1} 年\\n\\` \\${x}` `${a}${ {\"k\": \"}\"}[\"k\"] }` ``";
+        let mut lexer = Lexer::new(input);
+
+        assert_eq!(
+            lexer.next_token(),
+            Token::Template(vec![
+                TemplateSegment::Text(String::from("纪年 ")),
+                TemplateSegment::Code(String::from("危机纪年 + 1"), Span::new(10, 26, 1, 7)),
+                TemplateSegment::Text(String::from(" 年\n` ${x}")),
+            ])
+        );
+        assert_eq!(
+            lexer.next_token(),
+            Token::Template(vec![
+                TemplateSegment::Code(String::from("a"), Span::new(46, 47, 1, 33)),
+                TemplateSegment::Code(String::from(" {\"k\": \"}\"}[\"k\"] "), Span::new(50, 67, 1, 37)),
+            ])
+        );
+        assert_eq!(lexer.next_token(), Token::Template(vec![]));
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_unterminated_template() {
+        for input in ["`三体", "`${1 + 2`", "`${ \"}\" `", "`\\`"] {
+            let mut lexer = Lexer::new(input);
+            assert_eq!(
+                lexer.next_token(),
+                Token::Error(LexError::UnterminatedString('`')),
+                "{}",
+                input
+            );
+            assert_eq!(lexer.next_token(), Token::Eof);
+        }
+        assert_eq!(
+            Lexer::new("`\\q`").next_token(),
+            Token::Error(LexError::InvalidEscape(String::from("\\q")))
+        );
+    }
+
+    #[test]
+    fn test_block_comment() {
+        let input = "1 /* 面壁 /* 嵌套 */ 破壁 */ 2 /*/ still */ 3 /**/ 4 /* 广播\n多行 */ 5";
+        let mut lexer = Lexer::new(input);
+
+        for expect in 1..=5 {
+            assert_eq!(lexer.next_token(), Token::Int(expect));
+        }
+        assert_eq!(lexer.next_token(), Token::Eof);
+
+        for input in ["1 /* 未完", "/* /* */", "/*/"] {
+            let mut lexer = Lexer::new(input);
+            if input.starts_with('1') {
+                assert_eq!(lexer.next_token(), Token::Int(1));
+            }
+            assert_eq!(lexer.next_token(), Token::Error(LexError::UnterminatedComment), "{}", input);
+            assert_eq!(lexer.next_token(), Token::Eof);
+        }
+    }
+
+    #[test]
+    fn test_doc_comment() {
+        let input = "/// 宇宙的公理\r\n///第二行\n//// not a doc\n// nor this\n给 a 以 1; ///\n";
+        let mut lexer = Lexer::new(input);
+
+        let tests = vec![
+            Token::DocComment(String::from("宇宙的公理")),
+            Token::DocComment(String::from("第二行")),
+            Token::Let,
+            Token::Ident(String::from("a")),
+            Token::Assign,
+            Token::Int(1),
+            Token::Semicolon,
+            Token::DocComment(String::new()),
+            Token::Eof,
+        ];
+        for expect in tests {
+            assert_eq!(lexer.next_token(), expect);
+        }
+
+        let tokens = Lexer::new(input).lossless_tokens();
+        let joined: String = tokens.iter().map(|token| format!("{}{}", token.trivia, token.text)).collect();
+        assert_eq!(joined, input);
+    }
+
+    #[test]
+    fn test_full_width() {
+        let input = "给 a 以 【１２，３．５】；a【０】＝＝１２！＝ｂ｛ｋ：１｝＜＝＞　～ \"【】\" a１ `＄｛x｝${【】}`";
+        let mut lexer = Lexer::new(input);
+
+        let tests = vec![
+            Token::Let,
+            Token::Ident(String::from("a")),
+            Token::Assign,
+            Token::LBracket,
+            Token::Int(12),
+            Token::Comma,
+            Token::Float(3.5),
+            Token::RBracket,
+            Token::Semicolon,
+            Token::Ident(String::from("a")),
+            Token::LBracket,
+            Token::Int(0),
+            Token::RBracket,
+            Token::Equal,
+            Token::Int(12),
+            Token::NotEqual,
+            Token::Ident(String::from("ｂ")),
+            Token::LBrace,
+            Token::Ident(String::from("ｋ")),
+            Token::Colon,
+            Token::Int(1),
+            Token::RBrace,
+            Token::LTEQ,
+            Token::GT,
+            Token::Tilde,
+            Token::String(String::from("【】")),
+            Token::Ident(String::from("a1")),
+            Token::Template(vec![
+                TemplateSegment::Text(String::from("＄｛x｝")),
+                TemplateSegment::Code(String::from("【】"), Span::new(126, 132, 1, 53)),
+            ]),
+            Token::Eof,
+        ];
+        for expect in tests {
+            assert_eq!(lexer.next_token(), expect);
+        }
+    }
+
+    #[test]
+    fn test_strict_full_width() {
+        let mut lexer = Lexer::new("广播（１）; \"（）\"");
+        lexer.set_strict(true);
+
+        let tests = vec![
+            Token::Ident(String::from("广播")),
+            Token::Error(LexError::FullWidth('（')),
+            Token::Error(LexError::FullWidth('１')),
+            Token::Error(LexError::FullWidth('）')),
+            Token::Semicolon,
+            Token::String(String::from("（）")),
+            Token::Eof,
+        ];
+        for expect in tests {
+            assert_eq!(lexer.next_token(), expect);
+        }
+
+        let mut lexer = Lexer::new("【】");
+        lexer.set_strict(true);
+        let mut nested = lexer.nested("，", Span::default());
+        assert_eq!(nested.next_token(), Token::Error(LexError::FullWidth('，')));
+    }
+
+    #[test]
+    fn test_chinese_numeral() {
+        let input = "面壁 (危机纪年 < 四百) 给 三体 以 十一 + 二百零五 - 〇; 一万 三个 四百年 一百五";
+        let mut lexer = Lexer::new(input);
+
+        let tests = vec![
+            Token::While,
+            Token::LParen,
+            Token::Ident(String::from("危机纪年")),
+            Token::LT,
+            Token::Int(400),
+            Token::RParen,
+            Token::Let,
+            Token::Ident(String::from("三体")),
+            Token::Assign,
+            Token::Int(11),
+            Token::Plus,
+            Token::Int(205),
+            Token::Minus,
+            Token::Int(0),
+            Token::Semicolon,
+            Token::Int(10_000),
+            Token::Ident(String::from("三个")),
+            Token::Ident(String::from("四百年")),
+            Token::Error(LexError::InvalidNumeral(String::from("一百五"))),
+            Token::Eof,
+        ];
+        for expect in tests {
+            assert_eq!(lexer.next_token(), expect);
+        }
+    }
+
+    #[test]
+    fn test_radix_and_underscore() {
+        let tests = vec![
+            ("0x1F", Token::Int(31)),
+            ("0xdead_BEEF", Token::Int(0xdead_beef)),
+            ("0o17", Token::Int(15)),
+            ("0b1010", Token::Int(10)),
+            ("0b_1", Token::Int(1)),
+            ("1_000_000", Token::Int(1_000_000)),
+            ("1__0", Token::Int(10)),
+            ("1.234_5", Token::Float(1.234_5)),
+            ("6.67e1_1", Token::Float(6.67e11)),
+            ("0", Token::Int(0)),
+            ("0x7fff_ffff_ffff_ffff", Token::Int(i64::MAX)),
+            ("0b102", Token::Error(LexError::InvalidDigit(String::from("0b102"), '2'))),
+            ("0o8", Token::Error(LexError::InvalidDigit(String::from("0o8"), '8'))),
+            ("0xfg", Token::Error(LexError::InvalidDigit(String::from("0xfg"), 'g'))),
+            ("1_", Token::Error(LexError::TrailingUnderscore(String::from("1_")))),
+            ("1_.5", Token::Error(LexError::TrailingUnderscore(String::from("1_.5")))),
+            ("1_e5", Token::Error(LexError::TrailingUnderscore(String::from("1_e5")))),
+            ("0x1_", Token::Error(LexError::TrailingUnderscore(String::from("0x1_")))),
+            ("0x", Token::Error(LexError::MissingDigits(String::from("0x")))),
+            ("0b__", Token::Error(LexError::MissingDigits(String::from("0b__")))),
+            ("0x8000_0000_0000_0000", Token::Error(LexError::IntegerOutOfRange(String::from("0x8000_0000_0000_0000")))),
+        ];
+
+        for (input, expect) in tests {
+            let mut lexer = Lexer::new(input);
+            assert_eq!(lexer.next_token(), expect, "{}", input);
+        }
+
+        let mut lexer = Lexer::new("0b102;");
+        let spanned = lexer.next_spanned_token();
+        assert_eq!((spanned.span.start, spanned.span.end), (0, 5));
+        assert_eq!(lexer.next_token(), Token::Semicolon);
+    }
+
+    #[test]
+    fn test_logical_token() {
+        let mut lexer = Lexer::new("a && b || c 并且 d 或者 e");
+
+        let tests = vec![
+            Token::Ident(String::from("a")),
+            Token::And,
+            Token::Ident(String::from("b")),
+            Token::Or,
+            Token::Ident(String::from("c")),
+            Token::And,
+            Token::Ident(String::from("d")),
+            Token::Or,
+            Token::Ident(String::from("e")),
+            Token::Eof,
+        ];
+
+        for expect in tests {
+            assert_eq!(lexer.next_token(), expect);
+        }
+    }
+
+    #[test]
+    fn test_spanned_token() {
+        let mut lexer = Lexer::new("给 文明 以 5；\n  广播(文明)");
+
+        let tests = vec![
+            (Token::Let, 0, 3, 1, 1),
+            (Token::Ident(String::from("文明")), 4, 10, 1, 3),
+            (Token::Assign, 11, 14, 1, 6),
+            (Token::Int(5), 15, 16, 1, 8),
+            (Token::Semicolon, 16, 19, 1, 9),
+            (Token::Ident(String::from("广播")), 22, 28, 2, 3),
+            (Token::LParen, 28, 29, 2, 5),
+            (Token::Ident(String::from("文明")), 29, 35, 2, 6),
+            (Token::RParen, 35, 36, 2, 8),
+            (Token::Eof, 36, 36, 2, 9),
+        ];
+
+        for (token, start, end, line, column) in tests {
+            let spanned = lexer.next_spanned_token();
+            assert_eq!(spanned.token, token);
+            assert_eq!(
+                (spanned.span.start, spanned.span.end, spanned.span.line, spanned.span.column),
+                (start, end, line, column)
+            );
+        }
+
+        let mut lexer = Lexer::new("1 + 文明");
+        lexer.set_origin(Span::new(20, 20, 3, 1));
+        let spans: Vec<Span> = (0..3).map(|_| lexer.next_spanned_token().span).collect();
+        assert_eq!(
+            spans,
+            vec![Span::new(20, 21, 3, 1), Span::new(22, 23, 3, 3), Span::new(24, 30, 3, 5)]
+        );
+    }
+
     #[test]
     fn test_next_token() {
         let input = r#"let five = 5;
@@ -330,7 +1225,7 @@ let add = fn(x, y) {
 };
 
 let result = add(five, ten);
-!-/*5;
+!-/ *5;
 5 < 10 > 5;
 
 if (5 < 10) {
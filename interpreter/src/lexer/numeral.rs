@@ -0,0 +1,268 @@
+use crate::token::LexError;
+
+const ZERO: [char; 2] = ['零', '〇'];
+
+/// chars a chinese numeral is written with,
+/// a word made of nothing but these is a number, never an identifier
+pub fn is_numeral_char(c: char) -> bool {
+    digit(c).is_some() || unit(c).is_some() || large_unit(c).is_some()
+}
+
+fn digit(c: char) -> Option<i64> {
+    match c {
+        '零' | '〇' => Some(0),
+        '一' => Some(1),
+        '二' | '两' => Some(2),
+        '三' => Some(3),
+        '四' => Some(4),
+        '五' => Some(5),
+        '六' => Some(6),
+        '七' => Some(7),
+        '八' => Some(8),
+        '九' => Some(9),
+        _ => None,
+    }
+}
+
+fn unit(c: char) -> Option<i64> {
+    match c {
+        '十' => Some(10),
+        '百' => Some(100),
+        '千' => Some(1000),
+        _ => None,
+    }
+}
+
+fn large_unit(c: char) -> Option<i64> {
+    match c {
+        '万' => Some(10_000),
+        '亿' => Some(100_000_000),
+        _ => None,
+    }
+}
+
+/// the value of a chinese numeral, e.g. 三, 十一, 二百零五, 一万, 十二万三千亿,
+/// or digit by digit like 二〇二四
+pub fn parse_numeral(word: &str) -> Result<i64, LexError> {
+    let chars: Vec<char> = word.chars().collect();
+    let invalid = || LexError::InvalidNumeral(word.to_string());
+
+    if chars.iter().all(|&c| digit(c).is_some()) {
+        return chars
+            .iter()
+            .try_fold(0i64, |value, &c| value.checked_mul(10)?.checked_add(digit(c)?))
+            .ok_or(LexError::IntegerOutOfRange(word.to_string()));
+    }
+
+    parse_large(&chars).ok_or_else(invalid)
+}
+
+/// split at the last 亿, then at 万: everything before a large unit is multiplied by it
+fn parse_large(chars: &[char]) -> Option<i64> {
+    for large in ['亿', '万'] {
+        let Some(i) = chars.iter().rposition(|&c| c == large) else {
+            continue;
+        };
+        let scale = large_unit(large)?;
+        let (high, low) = (&chars[..i], &chars[i + 1..]);
+        if high.is_empty() {
+            return None;
+        }
+        let high = match large {
+            '亿' => parse_large(high)?,
+            _ => parse_section(high)?,
+        };
+        let low_value = match large {
+            '亿' => parse_large(low)?,
+            _ => parse_section(low)?,
+        };
+        // 一万五 is not 一万零五, a lower part missing its top place needs the 零
+        if !low.is_empty() && !ZERO.contains(&low[0]) && low_value < scale / 10 {
+            return None;
+        }
+        return high.checked_mul(scale)?.checked_add(low_value);
+    }
+
+    parse_section(chars)
+}
+
+/// a number below 一万 made of digits, 千 百 十 and 零
+fn parse_section(chars: &[char]) -> Option<i64> {
+    let mut value = 0;
+    let mut pending = None;
+    let mut last_unit = 10_000;
+    let mut after_zero = false;
+    // a digit right after 千 or 百 without a 零 between
+    let mut bare = false;
+
+    for (i, &c) in chars.iter().enumerate() {
+        if let Some(d) = digit(c) {
+            if pending.is_some() {
+                return None;
+            }
+            if d == 0 {
+                after_zero = true;
+                continue;
+            }
+            bare = last_unit > 10 && last_unit < 10_000 && !after_zero;
+            pending = Some(d);
+        } else if let Some(u) = unit(c) {
+            if u >= last_unit {
+                return None;
+            }
+            let multiplier = match pending.take() {
+                Some(d) => d,
+                // 十一 for 一十一, only at the very start
+                None if u == 10 && i == 0 => 1,
+                None => return None,
+            };
+            value += multiplier * u;
+            last_unit = u;
+            after_zero = false;
+        } else {
+            return None;
+        }
+    }
+
+    // 一百五 would read as 150 to some and 105 to others
+    if pending.is_some() && bare {
+        return None;
+    }
+    Some(value + pending.unwrap_or(0))
+}
+
+/// an integer written as a chinese numeral, the form parse_numeral reads back
+pub fn to_numeral(value: i64) -> String {
+    match value {
+        0 => String::from("零"),
+        _ if value < 0 => format!("负{}", format_large(value.unsigned_abs(), true)),
+        _ => format_large(value as u64, true),
+    }
+}
+
+fn format_large(value: u64, leading: bool) -> String {
+    for (large, scale) in [('亿', 100_000_000u64), ('万', 10_000)] {
+        if value < scale {
+            continue;
+        }
+        let (high, low) = (value / scale, value % scale);
+        let mut result = format_large(high, leading);
+        result.push(large);
+        if low > 0 {
+            if low < scale / 10 {
+                result.push('零');
+            }
+            result.push_str(&format_large(low, false));
+        }
+        return result;
+    }
+
+    format_section(value, leading)
+}
+
+fn format_section(value: u64, leading: bool) -> String {
+    const DIGITS: [char; 10] = ['零', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+    let mut result = String::new();
+    let mut zero = false;
+
+    for (unit, name) in [(1000, Some('千')), (100, Some('百')), (10, Some('十')), (1, None)] {
+        let d = (value / unit % 10) as usize;
+        if d == 0 {
+            zero = !result.is_empty();
+            continue;
+        }
+        if zero {
+            result.push('零');
+            zero = false;
+        }
+        // 十一 rather than 一十一, but 一百一十
+        if !(d == 1 && unit == 10 && leading && result.is_empty()) {
+            result.push(DIGITS[d]);
+        }
+        if let Some(name) = name {
+            result.push(name);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_numeral() {
+        let tests = vec![
+            ("〇", 0),
+            ("零", 0),
+            ("三", 3),
+            ("两", 2),
+            ("十", 10),
+            ("十一", 11),
+            ("二十", 20),
+            ("一百一十", 110),
+            ("二百零五", 205),
+            ("一千零五十", 1050),
+            ("四百", 400),
+            ("一万", 10_000),
+            ("十万零一十", 100_010),
+            ("一万零五百", 10_500),
+            ("三亿二千万", 320_000_000),
+            ("一万亿", 1_000_000_000_000),
+            ("十二万三千四百五十六亿七千八百九十万一千二百三十四", 12_345_678_901_234),
+            ("二〇二四", 2024),
+            ("一二三", 123),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(parse_numeral(input), Ok(expect), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_numeral() {
+        for input in ["万", "十十", "百", "一百五", "一万五", "五十百", "一十百", "二三十", "零十", "亿二"] {
+            assert_eq!(
+                parse_numeral(input),
+                Err(LexError::InvalidNumeral(String::from(input))),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            parse_numeral("九九九九九九九九九九九九九九九九九九九九"),
+            Err(LexError::IntegerOutOfRange(String::from("九九九九九九九九九九九九九九九九九九九九")))
+        );
+    }
+
+    #[test]
+    fn test_to_numeral() {
+        let tests = vec![
+            (0, "零"),
+            (7, "七"),
+            (10, "十"),
+            (11, "十一"),
+            (110, "一百一十"),
+            (205, "二百零五"),
+            (1050, "一千零五十"),
+            (10_000, "一万"),
+            (100_010, "十万零一十"),
+            (-400, "负四百"),
+            (320_000_000, "三亿二千万"),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(to_numeral(input), expect);
+        }
+    }
+
+    #[test]
+    fn test_numeral_round_trip() {
+        let mut values = vec![i64::MAX, 1_000_000_000_000, 100_000_001, 10_001, 1_001_000];
+        values.extend((0..3000).map(|i| i * 7919 % 100_000_007));
+        for value in values {
+            assert_eq!(parse_numeral(&to_numeral(value)), Ok(value), "{}", to_numeral(value));
+        }
+    }
+}
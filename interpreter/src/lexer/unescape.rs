@@ -1,3 +1,122 @@
+use crate::token::LexError;
+
+/// quote a string the way it is written in source, unescape reads it back
 pub fn escape_str(s: &str) -> String {
     format!("{:?}", s)
 }
+
+/// resolve the escapes in the body of a string literal (without its quotes)
+/// supported: \n \t \r \0 \\ \" \' \` \$ and \u{...} with 1 to 6 hex digits
+pub fn unescape(raw: &str) -> Result<String, LexError> {
+    let mut result = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+
+        let escaped = match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('`') => '`',
+            Some('$') => '$',
+            Some('u') => unescape_unicode(&mut chars)?,
+            Some(other) => return Err(LexError::InvalidEscape(format!("\\{}", other))),
+            None => return Err(LexError::InvalidEscape(String::from("\\"))),
+        };
+        result.push(escaped);
+    }
+
+    Ok(result)
+}
+
+/// the `{...}` part of \u{...}
+fn unescape_unicode(chars: &mut std::str::Chars) -> Result<char, LexError> {
+    let mut literal = String::from("\\u");
+
+    if chars.clone().next() != Some('{') {
+        return Err(LexError::InvalidUnicodeEscape(literal));
+    }
+    literal.push(chars.next().unwrap_or('{'));
+
+    let mut digits = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => {
+                literal.push('}');
+                break;
+            }
+            Some(ch) if ch.is_ascii_hexdigit() => {
+                literal.push(ch);
+                digits.push(ch);
+            }
+            Some(ch) => {
+                literal.push(ch);
+                return Err(LexError::InvalidUnicodeEscape(literal));
+            }
+            None => return Err(LexError::InvalidUnicodeEscape(literal)),
+        }
+    }
+
+    if digits.is_empty() || digits.len() > 6 {
+        return Err(LexError::InvalidUnicodeEscape(literal));
+    }
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or(LexError::InvalidUnicodeEscape(literal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unescape() {
+        let tests = vec![
+            (r#"a\nb"#, "a\nb"),
+            (r#"\t\r\0"#, "\t\r\0"),
+            (r#"\\ \" \'"#, "\\ \" '"),
+            (r#"\u{4E09}\u{4f53}"#, "三体"),
+            (r#"\u{1F30C}"#, "🌌"),
+            ("三体", "三体"),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(unescape(input), Ok(String::from(expect)));
+        }
+    }
+
+    #[test]
+    fn test_unescape_errors() {
+        let tests = vec![
+            (r#"\q"#, LexError::InvalidEscape(String::from(r#"\q"#))),
+            (r#"a\"#, LexError::InvalidEscape(String::from(r#"\"#))),
+            (r#"\u三"#, LexError::InvalidUnicodeEscape(String::from(r#"\u"#))),
+            (r#"\u{}"#, LexError::InvalidUnicodeEscape(String::from(r#"\u{}"#))),
+            (r#"\u{4E0g}"#, LexError::InvalidUnicodeEscape(String::from(r#"\u{4E0g"#))),
+            (r#"\u{4E09"#, LexError::InvalidUnicodeEscape(String::from(r#"\u{4E09"#))),
+            (r#"\u{D800}"#, LexError::InvalidUnicodeEscape(String::from(r#"\u{D800}"#))),
+            (r#"\u{110000}"#, LexError::InvalidUnicodeEscape(String::from(r#"\u{110000}"#))),
+            (r#"\u{0000041}"#, LexError::InvalidUnicodeEscape(String::from(r#"\u{0000041}"#))),
+        ];
+
+        for (input, expect) in tests {
+            assert_eq!(unescape(input), Err(expect));
+        }
+    }
+
+    #[test]
+    fn test_escape_round_trip() {
+        for s in ["", "三体", "a\nb\tc\r", "\"quoted\" 'single'", "back\\slash", "\0", "\u{301}é", "\u{200b}"] {
+            assert_eq!(unescape(&escape_str(s)[1..escape_str(s).len() - 1]), Ok(String::from(s)));
+        }
+    }
+}
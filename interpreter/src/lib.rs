@@ -3,6 +3,8 @@ pub mod lexer;
 pub mod ast;
 pub mod parser;
 pub mod evaluator;
+pub mod diagnostics;
+pub mod translator;
 
 pub fn add(left: usize, right: usize) -> usize {
     left + right
@@ -1,22 +1,40 @@
 use crate::ast::*;
 use crate::lexer::Lexer;
-use crate::token::Token;
+use crate::token::{LexError, Span, SpannedToken, TemplateSegment, Token};
 
 #[derive(Debug)]
 pub struct Parser {
     lexer: Lexer,
     current_token: Token,
+    current_span: Span,
     next_token: Token,
+    next_span: Span,
+    // `///` doc comments written right before the current and the next token
+    current_docs: Vec<String>,
+    next_docs: Vec<String>,
     errors: ParseErrors,
 }
 
 #[derive(Debug, Clone)]
 pub enum ParseError {
-    UnexpectedToken { want: Option<Token>, got: Token },
+    UnexpectedToken { want: Option<Token>, got: Token, span: Span },
+    Lex { error: LexError, span: Span },
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { span, .. } => *span,
+            ParseError::Lex { span, .. } => *span,
+        }
+    }
 }
 
 pub type ParseErrors = Vec<ParseError>;
 
+/// the positional and the `name = value` arguments of a call
+type CallArgs = (Vec<Expr>, Vec<(Ident, Expr)>);
+
 ///
 // Basic Implement
 ///
@@ -25,7 +43,11 @@ impl Parser {
         let mut parser = Parser {
             lexer,
             current_token: Token::Eof,
+            current_span: Span::default(),
             next_token: Token::Eof,
+            next_span: Span::default(),
+            current_docs: vec![],
+            next_docs: vec![],
             errors: vec![],
         };
 
@@ -41,7 +63,7 @@ impl Parser {
         while self.current_token != Token::Eof {
             match self.parse_stmt() {
                 Some(stmt) => program.push(stmt),
-                None => {}
+                None => self.synchronize(),
             }
             self.walk_token();
         }
@@ -55,8 +77,17 @@ impl Parser {
             Token::Let => self.parse_let_stmt(),
             Token::Const => self.parse_const_stmt(),
             Token::Return => self.parse_return_stmt(),
+            Token::Function => match self.next_token {
+                Token::Ident(_) => self.parse_function_stmt(),
+                _ => self.parse_expr_stmt(),
+            },
             Token::Ident(_) => match self.next_token {
-                Token::Assign => self.parse_reassign_stmt(),
+                Token::Assign
+                | Token::PlusAssign
+                | Token::MinusAssign
+                | Token::AsteriskAssign
+                | Token::SlashAssign
+                | Token::PercentAssign => self.parse_reassign_stmt(),
                 _ => self.parse_expr_stmt(),
             },
             Token::Break => self.parse_break_stmt(),
@@ -73,7 +104,47 @@ impl Parser {
 impl Parser {
     fn walk_token(&mut self) {
         self.current_token = self.next_token.clone();
-        self.next_token = self.lexer.next_token();
+        self.current_span = self.next_span;
+        self.current_docs = std::mem::take(&mut self.next_docs);
+        let mut spanned = self.lexer.next_spanned_token();
+        while let Token::DocComment(text) = spanned.token {
+            self.next_docs.push(text);
+            spanned = self.lexer.next_spanned_token();
+        }
+        let SpannedToken { token, span } = spanned;
+        if let Token::Error(ref error) = token {
+            self.errors.push(ParseError::Lex {
+                error: error.clone(),
+                span,
+            });
+        }
+        self.next_token = token;
+        self.next_span = span;
+    }
+
+    /// the token after next_token, doc comments skipped, without walking to it
+    fn peek_after_next(&self) -> Token {
+        let mut lexer = self.lexer.clone();
+        loop {
+            match lexer.next_token() {
+                Token::DocComment(_) => continue,
+                token => return token,
+            }
+        }
+    }
+
+    /// the doc comment above the current token, its lines joined,
+    /// docs above anything but a declaration are dropped
+    fn take_doc(&mut self) -> Option<String> {
+        if self.current_docs.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.current_docs).join("\n"))
+    }
+
+    /// the span from start up to the current token
+    fn span_from(&self, start: Span) -> Span {
+        start.to(self.current_span)
     }
 
     fn current_token_is(&mut self, tok: Token) -> bool {
@@ -98,10 +169,15 @@ impl Parser {
     }
 
     fn error_next_token(&mut self, tok: Token) {
-        self.errors.push(ParseError::UnexpectedToken {
-            want: Some(tok),
-            got: self.next_token.clone(),
-        });
+        self.error_unexpected(Some(tok), self.next_token.clone(), self.next_span);
+    }
+
+    /// lex errors are already reported by walk_token, so they are not reported twice
+    fn error_unexpected(&mut self, want: Option<Token>, got: Token, span: Span) {
+        if let Token::Error(_) = got {
+            return;
+        }
+        self.errors.push(ParseError::UnexpectedToken { want, got, span });
     }
 
     pub fn get_errors(&mut self) -> ParseErrors {
@@ -109,10 +185,49 @@ impl Parser {
     }
 
     fn error_no_prefix_parser(&mut self) {
-        self.errors.push(ParseError::UnexpectedToken {
-            want: None,
-            got: self.next_token.clone(),
-        });
+        self.error_unexpected(None, self.current_token.clone(), self.current_span);
+    }
+}
+
+///
+// Error Recovery Implement (panic mode)
+// 出错后跳过当前语句剩余的 token, 从下一条语句继续解析, 以便一次报告所有错误
+///
+impl Parser {
+    /// skip the rest of a broken stmt, so that the next walk_token lands on the next stmt
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+
+        loop {
+            match self.current_token {
+                Token::Eof => return,
+                Token::Semicolon | Token::Blank if depth == 0 => return,
+                Token::LBrace => depth += 1,
+                _ => {}
+            }
+
+            match self.next_token {
+                Token::Eof => return,
+                Token::RBrace if depth == 0 => return,
+                Token::RBrace => depth -= 1,
+                Token::Let
+                | Token::Const
+                | Token::Return
+                | Token::While
+                | Token::If
+                | Token::Break
+                | Token::Continue
+                    if depth == 0 =>
+                {
+                    return
+                }
+                // `法则 名字(...)` starts a declaration, `法则(...)` is only a function literal
+                Token::Function if depth == 0 && matches!(self.peek_after_next(), Token::Ident(_)) => return,
+                _ => {}
+            }
+
+            self.walk_token();
+        }
     }
 }
 
@@ -123,9 +238,14 @@ impl Parser {
 
     /// let
     fn parse_let_stmt(&mut self) -> Option<Stmt> {
+        let start = self.current_span;
+        let doc = self.take_doc();
         match &self.next_token {
             Token::Ident(_) => self.walk_token(),
-            _ => return None,
+            _ => {
+                self.error_next_token(Token::Ident(String::from("ident")));
+                return None;
+            }
         };
 
         let name = match self.parse_ident() {
@@ -149,14 +269,19 @@ impl Parser {
             self.walk_token();
         }
 
-        Some(Stmt::Let(name, expr))
+        Some(Stmt::Let(name, expr, doc, self.span_from(start)))
     }
 
     /// const
     fn parse_const_stmt(&mut self) -> Option<Stmt> {
+        let start = self.current_span;
+        let doc = self.take_doc();
         match &self.next_token {
             Token::Ident(_) => self.walk_token(),
-            _ => return None,
+            _ => {
+                self.error_next_token(Token::Ident(String::from("ident")));
+                return None;
+            }
         };
 
         let name = match self.parse_ident() {
@@ -180,33 +305,85 @@ impl Parser {
             self.walk_token();
         }
 
-        Some(Stmt::Const(name, expr))
+        Some(Stmt::Const(name, expr, doc, self.span_from(start)))
     }
 
-    /// reassign
+    /// reassign, compound assign desugars onto it: `x += 1` is `x = x + 1`
     fn parse_reassign_stmt(&mut self) -> Option<Stmt> {
+        let start = self.current_span;
         let name = match self.parse_ident() {
             Some(name) => name,
             None => return None,
         };
 
-        if !self.assert_next_token(Token::Assign) {
-            return None;
-        }
+        let compound = match &self.next_token {
+            Token::Assign => None,
+            tok => match Self::compound_assign_infix(tok) {
+                Some(infix) => Some(infix),
+                None => {
+                    self.error_next_token(Token::Assign);
+                    return None;
+                }
+            },
+        };
 
         self.walk_token();
         self.walk_token();
 
-        let expr = match self.parse_expr(Precedence::Lowest) {
-            Some(expr) => expr,
-            None => return None,
+        let mut expr = self.parse_expr(Precedence::Lowest)?;
+        if let Some(infix) = compound {
+            let target = Expr::Ident(name.clone(), start);
+            expr = Expr::Infix(infix, Box::new(target), Box::new(expr), self.span_from(start));
+        }
+
+        if self.next_token_is(Token::Semicolon) {
+            self.walk_token();
+        }
+
+        Some(Stmt::ReAssign(name, expr, self.span_from(start)))
+    }
+
+    /// index assign, the target must be an index chain rooted at an ident
+    /// e.g. a[0] = 1, 宇宙.星系[0].名字 = "银河", a[0] += 1
+    fn parse_index_assign_stmt(&mut self, start: Span, target: Expr) -> Option<Stmt> {
+        let compound = Self::compound_assign_infix(&self.next_token);
+        let (left, index) = match target {
+            Expr::Index(left, index, _) if Self::is_assignable(&left) => (*left, *index),
+            _ => {
+                self.error_unexpected(None, self.next_token.clone(), self.next_span);
+                return None;
+            }
         };
 
+        self.walk_token();
+        self.walk_token();
+
+        let expr = self.parse_expr(Precedence::Lowest)?;
+
         if self.next_token_is(Token::Semicolon) {
             self.walk_token();
         }
 
-        Some(Stmt::ReAssign(name, expr))
+        Some(Stmt::IndexAssign(left, index, compound, expr, self.span_from(start)))
+    }
+
+    fn compound_assign_infix(tok: &Token) -> Option<Infix> {
+        match tok {
+            Token::PlusAssign => Some(Infix::Plus),
+            Token::MinusAssign => Some(Infix::Minus),
+            Token::AsteriskAssign => Some(Infix::Multiply),
+            Token::SlashAssign => Some(Infix::Divide),
+            Token::PercentAssign => Some(Infix::Modulo),
+            _ => None,
+        }
+    }
+
+    fn is_assignable(expr: &Expr) -> bool {
+        match expr {
+            Expr::Ident(_, _) => true,
+            Expr::Index(left, _, _) => Self::is_assignable(left),
+            _ => false,
+        }
     }
 }
 
@@ -216,6 +393,7 @@ impl Parser {
 impl Parser {
     /// if expr
     fn parse_if_expr(&mut self) -> Option<Expr> {
+        let start = self.current_span;
         if !self.assert_next_token(Token::LParen) {
             return None;
         }
@@ -256,11 +434,13 @@ impl Parser {
             cond: Box::new(cond),
             consequence,
             alternative,
+            span: self.span_from(start),
         })
     }
 
     /// while expr
     fn parse_while_expr(&mut self) -> Option<Expr> {
+        let start = self.current_span;
         if !self.assert_next_token(Token::LParen) {
             return None;
         }
@@ -288,33 +468,35 @@ impl Parser {
         Some(Expr::While {
             cond: Box::new(cond),
             consequence,
+            span: self.span_from(start),
         })
     }
 
     /// break
     fn parse_break_stmt(&mut self) -> Option<Stmt> {
-        self.walk_token();
+        let start = self.current_span;
 
         if self.next_token_is(Token::Semicolon) {
             self.walk_token();
         }
 
-        Some(Stmt::Break)
+        Some(Stmt::Break(self.span_from(start)))
     }
 
     /// continue
     fn parse_continue_stmt(&mut self) -> Option<Stmt> {
-        self.walk_token();
+        let start = self.current_span;
 
         if self.next_token_is(Token::Semicolon) {
             self.walk_token();
         }
 
-        Some(Stmt::Continue)
+        Some(Stmt::Continue(self.span_from(start)))
     }
 
     /// return
     fn parse_return_stmt(&mut self) -> Option<Stmt> {
+        let start = self.current_span;
         self.walk_token();
 
         let expr = match self.parse_expr(Precedence::Lowest) {
@@ -326,7 +508,7 @@ impl Parser {
             self.walk_token();
         }
 
-        Some(Stmt::Return(expr))
+        Some(Stmt::Return(expr, self.span_from(start)))
     }
 
     fn parse_block_stmt(&mut self) -> BlockStmt {
@@ -341,7 +523,7 @@ impl Parser {
             }
             match self.parse_stmt() {
                 Some(stmt) => block.push(stmt),
-                None => {}
+                None => self.synchronize(),
             }
             self.walk_token();
         }
@@ -356,16 +538,21 @@ impl Parser {
 impl Parser {
     /// expr
     fn parse_expr_stmt(&mut self) -> Option<Stmt> {
+        let start = self.current_span;
         let expr = match self.parse_expr(Precedence::Lowest) {
             Some(expr) => expr,
             _ => return None,
         };
 
+        if self.next_token_is(Token::Assign) || Self::compound_assign_infix(&self.next_token).is_some() {
+            return self.parse_index_assign_stmt(start, expr);
+        }
+
         if self.next_token_is(Token::Semicolon) {
             self.walk_token();
         }
 
-        Some(Stmt::Expr(expr))
+        Some(Stmt::Expr(expr, self.span_from(start)))
     }
 
     /// parse expr ...
@@ -373,12 +560,15 @@ impl Parser {
         let mut left = match self.current_token {
             Token::Ident(_) => self.parse_ident_expr(),
             Token::Int(_) => self.parse_int_expr(),
+            Token::Float(_) => self.parse_float_expr(),
             Token::String(_) => self.parse_string_expr(),
+            Token::Template(_) => self.parse_template_expr(),
             Token::Bool(_) => self.parse_bool_expr(),
+            Token::Null => Some(Expr::Literal(Literal::Null, self.current_span)),
             Token::LBracket => self.parse_array_expr(),
             Token::LBrace => self.parse_hash_expr(),
             Token::LParen => self.parse_grouped_expr(),
-            Token::Bang | Token::Minus | Token::Plus => self.parse_prefix_expr(),
+            Token::Bang | Token::Minus | Token::Plus | Token::Tilde => self.parse_prefix_expr(),
             Token::If => self.parse_if_expr(),
             Token::While => self.parse_while_expr(),
             Token::Function => self.parse_function_expr(),
@@ -399,26 +589,35 @@ impl Parser {
                 | Token::Minus
                 | Token::Slash
                 | Token::Asterisk
+                | Token::Percent
+                | Token::Power
+                | Token::BitAnd
+                | Token::BitOr
+                | Token::BitXor
+                | Token::ShiftLeft
+                | Token::ShiftRight
                 | Token::Equal
                 | Token::NotEqual
                 | Token::LT
                 | Token::LTEQ
                 | Token::GT
-                | Token::GTEQ => {
+                | Token::GTEQ
+                | Token::And
+                | Token::Or => {
                     self.walk_token();
-                    left = self.parse_infix_expr(left.unwrap());
+                    left = self.parse_infix_expr(left?);
                 }
                 Token::LBracket => {
                     self.walk_token();
-                    left = self.parse_index_expr(left.unwrap());
+                    left = self.parse_index_expr(left?);
                 }
                 Token::Dot => {
                     self.walk_token();
-                    left = self.parse_dot_index_expr(left.unwrap());
+                    left = self.parse_dot_index_expr(left?);
                 }
                 Token::LParen => {
                     self.walk_token();
-                    left = self.parse_call_expr(left.unwrap());
+                    left = self.parse_call_expr(left?);
                 }
                 _ => return left,
             }
@@ -430,13 +629,22 @@ impl Parser {
 
     /// ident expr
     fn parse_ident_expr(&mut self) -> Option<Expr> {
-        self.parse_ident().map(Expr::Ident)
+        let span = self.current_span;
+        self.parse_ident().map(|ident| Expr::Ident(ident, span))
     }
 
     /// int expr
     fn parse_int_expr(&mut self) -> Option<Expr> {
         match self.current_token {
-            Token::Int(ref mut int) => Some(Expr::Literal(Literal::Int(*int))),
+            Token::Int(ref mut int) => Some(Expr::Literal(Literal::Int(*int), self.current_span)),
+            _ => None,
+        }
+    }
+
+    /// float expr
+    fn parse_float_expr(&mut self) -> Option<Expr> {
+        match self.current_token {
+            Token::Float(value) => Some(Expr::Literal(Literal::Float(value), self.current_span)),
             _ => None,
         }
     }
@@ -444,27 +652,61 @@ impl Parser {
     /// string expr
     fn parse_string_expr(&mut self) -> Option<Expr> {
         match self.current_token {
-            Token::String(ref mut s) => Some(Expr::Literal(Literal::String(s.clone()))),
+            Token::String(ref mut s) => Some(Expr::Literal(Literal::String(s.clone()), self.current_span)),
             _ => None,
         }
     }
 
+    /// template expr, every ${...} is parsed as a whole expr on its own
+    fn parse_template_expr(&mut self) -> Option<Expr> {
+        let segments = match self.current_token {
+            Token::Template(ref segments) => segments.clone(),
+            _ => return None,
+        };
+
+        let mut parts = vec![];
+        for segment in segments {
+            match segment {
+                TemplateSegment::Text(text) => parts.push(TemplatePart::Text(text)),
+                TemplateSegment::Code(code, span) => {
+                    let mut parser = Parser::new(self.lexer.nested(&code, span));
+                    let expr = parser.parse_expr(Precedence::Lowest);
+                    if expr.is_some() && parser.next_token != Token::Eof {
+                        parser.error_next_token(Token::RBrace);
+                    }
+                    // the embedded code ends at its closing }, not at the end of the input
+                    self.errors.extend(parser.errors.into_iter().map(|error| match error {
+                        ParseError::UnexpectedToken { want, got: Token::Eof, span } => {
+                            ParseError::UnexpectedToken { want, got: Token::RBrace, span }
+                        }
+                        error => error,
+                    }));
+                    parts.push(TemplatePart::Expr(expr?));
+                }
+            }
+        }
+
+        Some(Expr::Template(parts, self.current_span))
+    }
+
     /// boolean expr
     fn parse_bool_expr(&mut self) -> Option<Expr> {
         match self.current_token {
-            Token::Bool(value) => Some(Expr::Literal(Literal::Bool(value))),
+            Token::Bool(value) => Some(Expr::Literal(Literal::Bool(value), self.current_span)),
             _ => None,
         }
     }
 
     /// array expr
     fn parse_array_expr(&mut self) -> Option<Expr> {
+        let start = self.current_span;
         self.parse_expr_list(Token::RBracket)
-            .map(|list| Expr::Literal(Literal::Array(list)))
+            .map(|list| Expr::Literal(Literal::Array(list), self.span_from(start)))
     }
 
     /// hash expr
     fn parse_hash_expr(&mut self) -> Option<Expr> {
+        let start = self.current_span;
         let mut pairs = Vec::new();
 
         while !self.next_token_is(Token::RBrace) {
@@ -504,7 +746,7 @@ impl Parser {
 
         self.walk_token();
 
-        Some(Expr::Literal(Literal::Hash(pairs)))
+        Some(Expr::Literal(Literal::Hash(pairs), self.span_from(start)))
     }
 
     /// parse the expr list until get the end token
@@ -548,17 +790,22 @@ impl Parser {
 
     /// prefix expr
     fn parse_prefix_expr(&mut self) -> Option<Expr> {
+        let start = self.current_span;
         let prefix = match self.current_token {
             Token::Bang => Prefix::Not,
             Token::Minus => Prefix::Minus,
             Token::Plus => Prefix::Plus,
+            Token::Tilde => Prefix::BitNot,
             _ => return None,
         };
 
         self.walk_token();
 
         self.parse_expr(Precedence::Prefix)
-            .map(|expr| Expr::Prefix(prefix, Box::new(expr)))
+            .map(|expr| {
+                let span = start.to(expr.span());
+                Expr::Prefix(prefix, Box::new(expr), span)
+            })
     }
 
     /// infix expr (which means "中缀-表达式")
@@ -568,21 +815,37 @@ impl Parser {
             Token::Minus => Infix::Minus,
             Token::Slash => Infix::Divide,
             Token::Asterisk => Infix::Multiply,
+            Token::Percent => Infix::Modulo,
+            Token::Power => Infix::Power,
+            Token::BitAnd => Infix::BitAnd,
+            Token::BitOr => Infix::BitOr,
+            Token::BitXor => Infix::BitXor,
+            Token::ShiftLeft => Infix::ShiftLeft,
+            Token::ShiftRight => Infix::ShiftRight,
             Token::Equal => Infix::Equal,
             Token::NotEqual => Infix::NotEqual,
             Token::LT => Infix::LT,
             Token::LTEQ => Infix::LTEQ,
             Token::GT => Infix::GT,
             Token::GTEQ => Infix::GTEQ,
+            Token::And => Infix::And,
+            Token::Or => Infix::Or,
             _ => return None,
         };
 
-        let precedence = self.current_token_precedence();
+        // ** is right associative: its right side may hold another **
+        let precedence = match infix {
+            Infix::Power => Precedence::Prefix,
+            _ => self.current_token_precedence(),
+        };
 
         self.walk_token();
 
         self.parse_expr(precedence)
-            .map(|right_expr| Expr::Infix(infix, Box::new(left), Box::new(right_expr)))
+            .map(|right_expr| {
+                let span = left.span().to(right_expr.span());
+                Expr::Infix(infix, Box::new(left), Box::new(right_expr), span)
+            })
     }
 
     /// index expr
@@ -600,7 +863,8 @@ impl Parser {
 
         self.walk_token();
 
-        Some(Expr::Index(Box::new(left), Box::new(index)))
+        let span = self.span_from(left.span());
+        Some(Expr::Index(Box::new(left), Box::new(index), span))
     }
 
     /// dot index expr
@@ -609,10 +873,14 @@ impl Parser {
 
         match self.parse_ident() {
             Some(name) => match name {
-                Ident(str) => Some(Expr::Index(
-                    Box::new(left),
-                    Box::new(Expr::Literal(Literal::String(str))),
-                )),
+                Ident(str) => {
+                    let span = self.span_from(left.span());
+                    Some(Expr::Index(
+                        Box::new(left),
+                        Box::new(Expr::Literal(Literal::String(str), self.current_span)),
+                        span,
+                    ))
+                }
                 _ => return None,
             },
             None => return None,
@@ -640,6 +908,34 @@ impl Parser {
 impl Parser {
     /// function expr
     fn parse_function_expr(&mut self) -> Option<Expr> {
+        let start = self.current_span;
+        let (params, body) = self.parse_function_rest()?;
+
+        Some(Expr::Function {
+            params,
+            body,
+            span: self.span_from(start),
+        })
+    }
+
+    /// function declaration, `法则 名字(x) { ... }`
+    fn parse_function_stmt(&mut self) -> Option<Stmt> {
+        let start = self.current_span;
+        let doc = self.take_doc();
+        self.walk_token();
+
+        let name = self.parse_ident()?;
+        let (params, body) = self.parse_function_rest()?;
+
+        if self.next_token_is(Token::Semicolon) {
+            self.walk_token();
+        }
+
+        Some(Stmt::Function(name, params, body, doc, self.span_from(start)))
+    }
+
+    /// the `(params) { body }` after 法则 or its name
+    fn parse_function_rest(&mut self) -> Option<(Vec<Param>, BlockStmt)> {
         if !self.assert_next_token(Token::LParen) {
             return None;
         }
@@ -657,14 +953,11 @@ impl Parser {
 
         self.walk_token();
 
-        Some(Expr::Function {
-            params,
-            body: self.parse_block_stmt(),
-        })
+        Some((params, self.parse_block_stmt()))
     }
 
-    /// function args
-    fn parse_function_args(&mut self) -> Option<Vec<Ident>> {
+    /// function args, each `x`, `x = default`, or a last `...rest`
+    fn parse_function_args(&mut self) -> Option<Vec<Param>> {
         let mut args = vec![];
 
         if self.next_token_is(Token::RParen) {
@@ -674,17 +967,18 @@ impl Parser {
 
         self.walk_token();
 
-        match self.parse_ident() {
-            Some(ident) => args.push(ident),
+        match self.parse_function_arg() {
+            Some(param) => args.push(param),
             None => return None,
         };
 
-        while self.next_token_is(Token::Comma) {
+        // nothing may follow the rest parameter, not even a comma
+        while !matches!(args.last(), Some(Param::Rest(_))) && self.next_token_is(Token::Comma) {
             self.walk_token();
             self.walk_token();
 
-            match self.parse_ident() {
-                Some(ident) => args.push(ident),
+            match self.parse_function_arg() {
+                Some(param) => args.push(param),
                 None => return None,
             };
         }
@@ -698,15 +992,84 @@ impl Parser {
         Some(args)
     }
 
+    fn parse_function_arg(&mut self) -> Option<Param> {
+        if self.current_token_is(Token::Ellipsis) {
+            self.walk_token();
+            return self.parse_ident().map(Param::Rest);
+        }
+
+        let ident = self.parse_ident()?;
+        if !self.next_token_is(Token::Assign) {
+            return Some(Param::Required(ident));
+        }
+
+        self.walk_token();
+        self.walk_token();
+
+        let default = self.parse_expr(Precedence::Lowest)?;
+        Some(Param::Default(ident, default))
+    }
+
+    /// call args, positional ones and `name = value` ones
+    fn parse_call_args(&mut self) -> Option<CallArgs> {
+        let mut args = vec![];
+        let mut named = vec![];
+
+        if self.next_token_is(Token::RParen) {
+            self.walk_token();
+            return Some((args, named));
+        }
+
+        self.walk_token();
+
+        self.parse_call_arg(&mut args, &mut named)?;
+
+        while self.next_token_is(Token::Comma) {
+            self.walk_token();
+            self.walk_token();
+            if self.current_token_is(Token::Eof) {
+                self.error_next_token(Token::Ident(String::from("ident")));
+                return None;
+            }
+
+            self.parse_call_arg(&mut args, &mut named)?;
+        }
+
+        if !self.assert_next_token(Token::RParen) {
+            return None;
+        }
+
+        self.walk_token();
+
+        Some((args, named))
+    }
+
+    fn parse_call_arg(&mut self, args: &mut Vec<Expr>, named: &mut Vec<(Ident, Expr)>) -> Option<()> {
+        match (&self.current_token, &self.next_token) {
+            (Token::Ident(name), Token::Assign) => {
+                let name = Ident(name.clone());
+                self.walk_token();
+                self.walk_token();
+                named.push((name, self.parse_expr(Precedence::Lowest)?));
+            }
+            _ => args.push(self.parse_expr(Precedence::Lowest)?),
+        }
+        Some(())
+    }
+
     fn parse_call_expr(&mut self, func_name: Expr) -> Option<Expr> {
-        let args = match self.parse_expr_list(Token::RParen) {
+        let (args, named) = match self.parse_call_args() {
             Some(args) => args,
             None => return None,
         };
 
+        let span = self.span_from(func_name.span());
+
         Some(Expr::Call {
             func: Box::new(func_name),
             args,
+            named,
+            span,
         })
     }
 }
@@ -717,11 +1080,18 @@ impl Parser {
 impl Parser {
     fn token_to_precedence(tok: &Token) -> Precedence {
         match tok {
+            Token::Or => Precedence::LogicalOr,
+            Token::And => Precedence::LogicalAnd,
             Token::Equal | Token::NotEqual => Precedence::Equals,
             Token::LT | Token::LTEQ => Precedence::LessGreater,
             Token::GT | Token::GTEQ => Precedence::LessGreater,
+            Token::BitOr => Precedence::BitOr,
+            Token::BitXor => Precedence::BitXor,
+            Token::BitAnd => Precedence::BitAnd,
+            Token::ShiftLeft | Token::ShiftRight => Precedence::Shift,
             Token::Plus | Token::Minus => Precedence::Sum,
-            Token::Slash | Token::Asterisk => Precedence::Product,
+            Token::Slash | Token::Asterisk | Token::Percent => Precedence::Product,
+            Token::Power => Precedence::Power,
             Token::LBracket => Precedence::Index,
             Token::Dot => Precedence::Index,
             Token::LParen => Precedence::Call,
@@ -750,7 +1120,14 @@ impl Parser {
     fn parse_ident(&mut self) -> Option<Ident> {
         match self.current_token {
             Token::Ident(ref mut ident) => Some(Ident(ident.clone())),
-            _ => None,
+            _ => {
+                self.error_unexpected(
+                    Some(Token::Ident(String::from("ident"))),
+                    self.current_token.clone(),
+                    self.current_span,
+                );
+                None
+            }
         }
     }
 }
@@ -761,11 +1138,18 @@ mod tests {
     use crate::ast::Ident;
     use crate::ast::Infix;
     use crate::ast::Literal;
+    use crate::ast::Param;
     use crate::ast::Prefix;
+    use crate::ast::Span;
     use crate::ast::Stmt;
+    use crate::ast::strip_spans;
+    use crate::ast::TemplatePart;
 
+    use super::LexError;
     use super::Lexer;
+    use super::ParseError;
     use super::Parser;
+    use super::Token;
 
     ///
     // cases from 2015
@@ -810,27 +1194,28 @@ if (x) {
         "#;
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![
-                Stmt::Expr(Expr::Literal(Literal::Int(1000))),
+                Stmt::Expr(Expr::Literal(Literal::Int(1000), Span::default()), Span::default()),
                 Stmt::Blank,
-                Stmt::Expr(Expr::Literal(Literal::Int(1000))),
+                Stmt::Expr(Expr::Literal(Literal::Int(1000), Span::default()), Span::default()),
                 Stmt::Blank,
                 Stmt::Blank,
-                Stmt::Expr(Expr::Literal(Literal::Int(1000))),
+                Stmt::Expr(Expr::Literal(Literal::Int(1000), Span::default()), Span::default()),
                 Stmt::Blank,
                 Stmt::Expr(Expr::If {
-                    cond: Box::new(Expr::Ident(Ident(String::from("x")))),
+                    cond: Box::new(Expr::Ident(Ident(String::from("x")), Span::default())),
                     consequence: vec![
                         Stmt::Blank,
-                        Stmt::Expr(Expr::Ident(Ident(String::from("x")))),
+                        Stmt::Expr(Expr::Ident(Ident(String::from("x")), Span::default()), Span::default()),
                         Stmt::Blank,
                     ],
                     alternative: None,
-                }),
+                    span: Span::default(),
+                }, Span::default()),
             ],
             program,
         );
@@ -845,16 +1230,18 @@ let foobar = 838383;
         "#;
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![
-                Stmt::Let(Ident(String::from("x")), Expr::Literal(Literal::Int(5))),
-                Stmt::Let(Ident(String::from("y")), Expr::Literal(Literal::Int(10))),
+                Stmt::Let(Ident(String::from("x")), Expr::Literal(Literal::Int(5), Span::default()), None, Span::default()),
+                Stmt::Let(Ident(String::from("y")), Expr::Literal(Literal::Int(10), Span::default()), None, Span::default()),
                 Stmt::Let(
                     Ident(String::from("foobar")),
-                    Expr::Literal(Literal::Int(838383)),
+                    Expr::Literal(Literal::Int(838383), Span::default()),
+                    None,
+                    Span::default(),
                 ),
             ],
             program,
@@ -870,16 +1257,18 @@ const foobar = 838383;
         "#;
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![
-                Stmt::Const(Ident(String::from("x")), Expr::Literal(Literal::Int(5))),
-                Stmt::Const(Ident(String::from("y")), Expr::Literal(Literal::Int(10))),
+                Stmt::Const(Ident(String::from("x")), Expr::Literal(Literal::Int(5), Span::default()), None, Span::default()),
+                Stmt::Const(Ident(String::from("y")), Expr::Literal(Literal::Int(10), Span::default()), None, Span::default()),
                 Stmt::Const(
                     Ident(String::from("foobar")),
-                    Expr::Literal(Literal::Int(838383)),
+                    Expr::Literal(Literal::Int(838383), Span::default()),
+                    None,
+                    Span::default(),
                 ),
             ],
             program,
@@ -895,22 +1284,119 @@ foobar = 838383;
         "#;
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![
-                Stmt::ReAssign(Ident(String::from("x")), Expr::Literal(Literal::Int(5))),
-                Stmt::ReAssign(Ident(String::from("y")), Expr::Literal(Literal::Int(10))),
+                Stmt::ReAssign(Ident(String::from("x")), Expr::Literal(Literal::Int(5), Span::default()), Span::default()),
+                Stmt::ReAssign(Ident(String::from("y")), Expr::Literal(Literal::Int(10), Span::default()), Span::default()),
                 Stmt::ReAssign(
                     Ident(String::from("foobar")),
-                    Expr::Literal(Literal::Int(838383)),
+                    Expr::Literal(Literal::Int(838383), Span::default()),
+                    Span::default(),
+                ),
+            ],
+            program,
+        );
+    }
+
+    #[test]
+    fn test_index_assign_stmt() {
+        let input = r#"
+a[0] = 1;
+宇宙.星系[0].名字 = "银河";
+        "#;
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        check_parse_errors(&mut parser);
+        assert_eq!(
+            vec![
+                Stmt::IndexAssign(
+                    Expr::Ident(Ident(String::from("a")), Span::default()),
+                    Expr::Literal(Literal::Int(0), Span::default()),
+                    None,
+                    Expr::Literal(Literal::Int(1), Span::default()),
+                    Span::default(),
+                ),
+                Stmt::IndexAssign(
+                    Expr::Index(
+                        Box::new(Expr::Index(
+                            Box::new(Expr::Ident(Ident(String::from("宇宙")), Span::default())),
+                            Box::new(Expr::Literal(Literal::String(String::from("星系")), Span::default())),
+                            Span::default(),
+                        )),
+                        Box::new(Expr::Literal(Literal::Int(0), Span::default())),
+                        Span::default(),
+                    ),
+                    Expr::Literal(Literal::String(String::from("名字")), Span::default()),
+                    None,
+                    Expr::Literal(Literal::String(String::from("银河")), Span::default()),
+                    Span::default(),
+                ),
+            ],
+            program,
+        );
+    }
+
+    #[test]
+    fn test_compound_assign_stmt() {
+        let input = r#"
+x += 1;
+纪年 降维以 2;
+a[0] *= 3;
+        "#;
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        check_parse_errors(&mut parser);
+        assert_eq!(
+            vec![
+                Stmt::ReAssign(
+                    Ident(String::from("x")),
+                    Expr::Infix(
+                        Infix::Plus,
+                        Box::new(Expr::Ident(Ident(String::from("x")), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(1), Span::default())),
+                        Span::default(),
+                    ),
+                    Span::default(),
+                ),
+                Stmt::ReAssign(
+                    Ident(String::from("纪年")),
+                    Expr::Infix(
+                        Infix::Minus,
+                        Box::new(Expr::Ident(Ident(String::from("纪年")), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(2), Span::default())),
+                        Span::default(),
+                    ),
+                    Span::default(),
+                ),
+                Stmt::IndexAssign(
+                    Expr::Ident(Ident(String::from("a")), Span::default()),
+                    Expr::Literal(Literal::Int(0), Span::default()),
+                    Some(Infix::Multiply),
+                    Expr::Literal(Literal::Int(3), Span::default()),
+                    Span::default(),
                 ),
             ],
             program,
         );
     }
 
+    #[test]
+    fn test_invalid_assign_target() {
+        for input in ["1 = 2;", "f()[0] = 1;", "[1][0] = 2;", "1 += 2;", "f() -= 1;"] {
+            let mut parser = Parser::new(Lexer::new(input));
+            parser.parse();
+
+            assert!(!parser.get_errors().is_empty(), "{} should not parse", input);
+        }
+    }
+
     #[test]
     fn test_return_stmt() {
         let input = r#"
@@ -920,14 +1406,14 @@ return 993322;
         "#;
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![
-                Stmt::Return(Expr::Literal(Literal::Int(5))),
-                Stmt::Return(Expr::Literal(Literal::Int(10))),
-                Stmt::Return(Expr::Literal(Literal::Int(993322))),
+                Stmt::Return(Expr::Literal(Literal::Int(5), Span::default()), Span::default()),
+                Stmt::Return(Expr::Literal(Literal::Int(10), Span::default()), Span::default()),
+                Stmt::Return(Expr::Literal(Literal::Int(993322), Span::default()), Span::default()),
             ],
             program,
         );
@@ -938,11 +1424,11 @@ return 993322;
         let input = "foobar;";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
-            vec![Stmt::Expr(Expr::Ident(Ident(String::from("foobar"))))],
+            vec![Stmt::Expr(Expr::Ident(Ident(String::from("foobar")), Span::default()), Span::default())],
             program,
         );
     }
@@ -952,10 +1438,21 @@ return 993322;
         let input = "5;";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
+
+        check_parse_errors(&mut parser);
+        assert_eq!(vec![Stmt::Expr(Expr::Literal(Literal::Int(5), Span::default()), Span::default())], program,);
+    }
+
+    #[test]
+    fn test_float_literal_expr() {
+        let input = "2.5;";
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
-        assert_eq!(vec![Stmt::Expr(Expr::Literal(Literal::Int(5)))], program,);
+        assert_eq!(vec![Stmt::Expr(Expr::Literal(Literal::Float(2.5), Span::default()), Span::default())], program,);
     }
 
     #[test]
@@ -963,13 +1460,13 @@ return 993322;
         let input = "\"hello world\";";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![Stmt::Expr(Expr::Literal(Literal::String(String::from(
                 "hello world",
-            ))))],
+            )), Span::default()), Span::default())],
             program,
         );
     }
@@ -977,13 +1474,13 @@ return 993322;
     #[test]
     fn test_boolean_literal_expr() {
         let tests = vec![
-            ("true;", Stmt::Expr(Expr::Literal(Literal::Bool(true)))),
-            ("false;", Stmt::Expr(Expr::Literal(Literal::Bool(false)))),
+            ("true;", Stmt::Expr(Expr::Literal(Literal::Bool(true), Span::default()), Span::default())),
+            ("false;", Stmt::Expr(Expr::Literal(Literal::Bool(false), Span::default()), Span::default())),
         ];
 
         for (input, expect) in tests {
             let mut parser = Parser::new(Lexer::new(input));
-            let program = parser.parse();
+            let program = strip_spans(parser.parse());
 
             check_parse_errors(&mut parser);
             assert_eq!(vec![expect], program);
@@ -995,23 +1492,25 @@ return 993322;
         let input = "[1, 2 * 2, 3 + 3]";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![Stmt::Expr(Expr::Literal(Literal::Array(vec![
-                Expr::Literal(Literal::Int(1)),
+                Expr::Literal(Literal::Int(1), Span::default()),
                 Expr::Infix(
                     Infix::Multiply,
-                    Box::new(Expr::Literal(Literal::Int(2))),
-                    Box::new(Expr::Literal(Literal::Int(2))),
+                    Box::new(Expr::Literal(Literal::Int(2), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(2), Span::default())),
+                    Span::default(),
                 ),
                 Expr::Infix(
                     Infix::Plus,
-                    Box::new(Expr::Literal(Literal::Int(3))),
-                    Box::new(Expr::Literal(Literal::Int(3))),
+                    Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                    Span::default(),
                 ),
-            ])))],
+            ]), Span::default()), Span::default())],
             program,
         );
     }
@@ -1019,65 +1518,68 @@ return 993322;
     #[test]
     fn test_hash_literal_expr() {
         let tests = vec![
-            ("{}", Stmt::Expr(Expr::Literal(Literal::Hash(vec![])))),
+            ("{}", Stmt::Expr(Expr::Literal(Literal::Hash(vec![]), Span::default()), Span::default())),
             (
                 "{\"one\": 1, \"two\": 2, \"three\": 3}",
                 Stmt::Expr(Expr::Literal(Literal::Hash(vec![
                     (
-                        Expr::Literal(Literal::String(String::from("one"))),
-                        Expr::Literal(Literal::Int(1)),
+                        Expr::Literal(Literal::String(String::from("one")), Span::default()),
+                        Expr::Literal(Literal::Int(1), Span::default()),
                     ),
                     (
-                        Expr::Literal(Literal::String(String::from("two"))),
-                        Expr::Literal(Literal::Int(2)),
+                        Expr::Literal(Literal::String(String::from("two")), Span::default()),
+                        Expr::Literal(Literal::Int(2), Span::default()),
                     ),
                     (
-                        Expr::Literal(Literal::String(String::from("three"))),
-                        Expr::Literal(Literal::Int(3)),
+                        Expr::Literal(Literal::String(String::from("three")), Span::default()),
+                        Expr::Literal(Literal::Int(3), Span::default()),
                     ),
-                ]))),
+                ]), Span::default()), Span::default()),
             ),
             (
                 "{\"one\": 0 + 1, \"two\": 10 - 8, \"three\": 15 / 5}",
                 Stmt::Expr(Expr::Literal(Literal::Hash(vec![
                     (
-                        Expr::Literal(Literal::String(String::from("one"))),
+                        Expr::Literal(Literal::String(String::from("one")), Span::default()),
                         Expr::Infix(
                             Infix::Plus,
-                            Box::new(Expr::Literal(Literal::Int(0))),
-                            Box::new(Expr::Literal(Literal::Int(1))),
+                            Box::new(Expr::Literal(Literal::Int(0), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(1), Span::default())),
+                            Span::default(),
                         ),
                     ),
                     (
-                        Expr::Literal(Literal::String(String::from("two"))),
+                        Expr::Literal(Literal::String(String::from("two")), Span::default()),
                         Expr::Infix(
                             Infix::Minus,
-                            Box::new(Expr::Literal(Literal::Int(10))),
-                            Box::new(Expr::Literal(Literal::Int(8))),
+                            Box::new(Expr::Literal(Literal::Int(10), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(8), Span::default())),
+                            Span::default(),
                         ),
                     ),
                     (
-                        Expr::Literal(Literal::String(String::from("three"))),
+                        Expr::Literal(Literal::String(String::from("three")), Span::default()),
                         Expr::Infix(
                             Infix::Divide,
-                            Box::new(Expr::Literal(Literal::Int(15))),
-                            Box::new(Expr::Literal(Literal::Int(5))),
+                            Box::new(Expr::Literal(Literal::Int(15), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                            Span::default(),
                         ),
                     ),
-                ]))),
+                ]), Span::default()), Span::default()),
             ),
             (
                 "{key: \"value\"}",
                 Stmt::Expr(Expr::Literal(Literal::Hash(vec![(
-                    Expr::Ident(Ident(String::from("key"))),
-                    Expr::Literal(Literal::String(String::from("value"))),
-                )]))),
+                    Expr::Ident(Ident(String::from("key")), Span::default()),
+                    Expr::Literal(Literal::String(String::from("value")), Span::default()),
+                )]), Span::default()), Span::default()),
             ),
         ];
 
         for (input, expect) in tests {
             let mut parser = Parser::new(Lexer::new(input));
-            let program = parser.parse();
+            let program = strip_spans(parser.parse());
 
             check_parse_errors(&mut parser);
             assert_eq!(vec![expect], program);
@@ -1089,18 +1591,20 @@ return 993322;
         let input = "myArray[1 + 1]";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![Stmt::Expr(Expr::Index(
-                Box::new(Expr::Ident(Ident(String::from("myArray")))),
+                Box::new(Expr::Ident(Ident(String::from("myArray")), Span::default())),
                 Box::new(Expr::Infix(
                     Infix::Plus,
-                    Box::new(Expr::Literal(Literal::Int(1))),
-                    Box::new(Expr::Literal(Literal::Int(1))),
+                    Box::new(Expr::Literal(Literal::Int(1), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(1), Span::default())),
+                    Span::default(),
                 )),
-            ))],
+                Span::default(),
+            ), Span::default())],
             program
         );
     }
@@ -1110,14 +1614,15 @@ return 993322;
         let input = "myHash.key";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![Stmt::Expr(Expr::Index(
-                Box::new(Expr::Ident(Ident(String::from("myHash")))),
-                Box::new(Expr::Literal(Literal::String(String::from("key")))),
-            ))],
+                Box::new(Expr::Ident(Ident(String::from("myHash")), Span::default())),
+                Box::new(Expr::Literal(Literal::String(String::from("key")), Span::default())),
+                Span::default(),
+            ), Span::default())],
             program
         );
     }
@@ -1129,28 +1634,31 @@ return 993322;
                 "!5;",
                 Stmt::Expr(Expr::Prefix(
                     Prefix::Not,
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "-15;",
                 Stmt::Expr(Expr::Prefix(
                     Prefix::Minus,
-                    Box::new(Expr::Literal(Literal::Int(15))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(15), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "+15;",
                 Stmt::Expr(Expr::Prefix(
                     Prefix::Plus,
-                    Box::new(Expr::Literal(Literal::Int(15))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(15), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
         ];
 
         for (input, expect) in tests {
             let mut parser = Parser::new(Lexer::new(input));
-            let program = parser.parse();
+            let program = strip_spans(parser.parse());
 
             check_parse_errors(&mut parser);
             assert_eq!(vec![expect], program);
@@ -1164,87 +1672,97 @@ return 993322;
                 "5 + 5;",
                 Stmt::Expr(Expr::Infix(
                     Infix::Plus,
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 - 5;",
                 Stmt::Expr(Expr::Infix(
                     Infix::Minus,
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 * 5;",
                 Stmt::Expr(Expr::Infix(
                     Infix::Multiply,
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 / 5;",
                 Stmt::Expr(Expr::Infix(
                     Infix::Divide,
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 > 5;",
                 Stmt::Expr(Expr::Infix(
                     Infix::GT,
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 < 5;",
                 Stmt::Expr(Expr::Infix(
                     Infix::LT,
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 == 5;",
                 Stmt::Expr(Expr::Infix(
                     Infix::Equal,
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 != 5;",
                 Stmt::Expr(Expr::Infix(
                     Infix::NotEqual,
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 >= 5;",
                 Stmt::Expr(Expr::Infix(
                     Infix::GTEQ,
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 <= 5;",
                 Stmt::Expr(Expr::Infix(
                     Infix::LTEQ,
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                    Box::new(Expr::Literal(Literal::Int(5))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
         ];
 
         for (input, expect) in tests {
             let mut parser = Parser::new(Lexer::new(input));
-            let program = parser.parse();
+            let program = strip_spans(parser.parse());
 
             check_parse_errors(&mut parser);
             assert_eq!(vec![expect], program);
@@ -1256,19 +1774,21 @@ return 993322;
         let input = "if (x < y) { x }";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![Stmt::Expr(Expr::If {
                 cond: Box::new(Expr::Infix(
                     Infix::LT,
-                    Box::new(Expr::Ident(Ident(String::from("x")))),
-                    Box::new(Expr::Ident(Ident(String::from("y")))),
+                    Box::new(Expr::Ident(Ident(String::from("x")), Span::default())),
+                    Box::new(Expr::Ident(Ident(String::from("y")), Span::default())),
+                    Span::default(),
                 )),
-                consequence: vec![Stmt::Expr(Expr::Ident(Ident(String::from("x"))))],
+                consequence: vec![Stmt::Expr(Expr::Ident(Ident(String::from("x")), Span::default()), Span::default())],
                 alternative: None,
-            })],
+                span: Span::default(),
+            }, Span::default())],
             program,
         );
     }
@@ -1278,19 +1798,21 @@ return 993322;
         let input = "if (x < y) { x } else { y }";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![Stmt::Expr(Expr::If {
                 cond: Box::new(Expr::Infix(
                     Infix::LT,
-                    Box::new(Expr::Ident(Ident(String::from("x")))),
-                    Box::new(Expr::Ident(Ident(String::from("y")))),
+                    Box::new(Expr::Ident(Ident(String::from("x")), Span::default())),
+                    Box::new(Expr::Ident(Ident(String::from("y")), Span::default())),
+                    Span::default(),
                 )),
-                consequence: vec![Stmt::Expr(Expr::Ident(Ident(String::from("x"))))],
-                alternative: Some(vec![Stmt::Expr(Expr::Ident(Ident(String::from("y"))))]),
-            })],
+                consequence: vec![Stmt::Expr(Expr::Ident(Ident(String::from("x")), Span::default()), Span::default())],
+                alternative: Some(vec![Stmt::Expr(Expr::Ident(Ident(String::from("y")), Span::default()), Span::default())]),
+                span: Span::default(),
+            }, Span::default())],
             program,
         );
     }
@@ -1300,18 +1822,20 @@ return 993322;
         let input = "fn(x, y) { x + y; }";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![Stmt::Expr(Expr::Function {
-                params: vec![Ident(String::from("x")), Ident(String::from("y"))],
+                params: vec![Param::Required(Ident(String::from("x"))), Param::Required(Ident(String::from("y")))],
                 body: vec![Stmt::Expr(Expr::Infix(
                     Infix::Plus,
-                    Box::new(Expr::Ident(Ident(String::from("x")))),
-                    Box::new(Expr::Ident(Ident(String::from("y")))),
-                ))],
-            })],
+                    Box::new(Expr::Ident(Ident(String::from("x")), Span::default())),
+                    Box::new(Expr::Ident(Ident(String::from("y")), Span::default())),
+                    Span::default(),
+                ), Span::default())],
+                span: Span::default(),
+            }, Span::default())],
             program,
         );
     }
@@ -1320,57 +1844,153 @@ return 993322;
     fn test_function_args() {
         let tests = vec![
             ("fn() {};", vec![]),
-            ("fn(x) {};", vec![Ident(String::from("x"))]),
+            ("fn(x) {};", vec![Param::Required(Ident(String::from("x")))]),
             (
                 "fn(x, y, z) {};",
                 vec![
-                    Ident(String::from("x")),
-                    Ident(String::from("y")),
-                    Ident(String::from("z")),
+                    Param::Required(Ident(String::from("x"))),
+                    Param::Required(Ident(String::from("y"))),
+                    Param::Required(Ident(String::from("z"))),
                 ],
             ),
         ];
 
         for (input, expect) in tests {
             let mut parser = Parser::new(Lexer::new(input));
-            let program = parser.parse();
+            let program = strip_spans(parser.parse());
 
             check_parse_errors(&mut parser);
             assert_eq!(
                 vec![Stmt::Expr(Expr::Function {
                     params: expect,
                     body: vec![],
-                })],
+                    span: Span::default(),
+                }, Span::default())],
                 program,
             );
         }
     }
 
+    #[test]
+    fn test_function_default_and_rest_params() {
+        let input = "fn(a, b = 1, ...rest) {}";
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        check_parse_errors(&mut parser);
+        assert_eq!(
+            vec![Stmt::Expr(Expr::Function {
+                params: vec![
+                    Param::Required(Ident(String::from("a"))),
+                    Param::Default(Ident(String::from("b")), Expr::Literal(Literal::Int(1), Span::default())),
+                    Param::Rest(Ident(String::from("rest"))),
+                ],
+                body: vec![],
+                span: Span::default(),
+            }, Span::default())],
+            program,
+        );
+
+        // the rest parameter comes last
+        let mut parser = Parser::new(Lexer::new("fn(...rest, a) {}"));
+        parser.parse();
+        match &parser.get_errors()[0] {
+            ParseError::UnexpectedToken { want, got, .. } => {
+                assert_eq!((want, got), (&Some(Token::RParen), &Token::Comma))
+            }
+            error => panic!("unexpected error {:?}", error),
+        }
+    }
+
+    #[test]
+    fn test_call_named_args() {
+        let input = "f(1, b = 2, a == 3);";
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        check_parse_errors(&mut parser);
+        assert_eq!(
+            vec![Stmt::Expr(Expr::Call {
+                func: Box::new(Expr::Ident(Ident(String::from("f")), Span::default())),
+                args: vec![
+                    Expr::Literal(Literal::Int(1), Span::default()),
+                    Expr::Infix(
+                        Infix::Equal,
+                        Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                        Span::default(),
+                    ),
+                ],
+                named: vec![(Ident(String::from("b")), Expr::Literal(Literal::Int(2), Span::default()))],
+                span: Span::default(),
+            }, Span::default())],
+            program,
+        );
+    }
+
+    #[test]
+    fn test_function_stmt() {
+        let input = "法则 加(x, y) { x + y } 法则(x) { x };";
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        check_parse_errors(&mut parser);
+        assert_eq!(
+            vec![
+                Stmt::Function(
+                    Ident(String::from("加")),
+                    vec![Param::Required(Ident(String::from("x"))), Param::Required(Ident(String::from("y")))],
+                    vec![Stmt::Expr(Expr::Infix(
+                        Infix::Plus,
+                        Box::new(Expr::Ident(Ident(String::from("x")), Span::default())),
+                        Box::new(Expr::Ident(Ident(String::from("y")), Span::default())),
+                        Span::default(),
+                    ), Span::default())],
+                    None,
+                    Span::default(),
+                ),
+                Stmt::Expr(Expr::Function {
+                    params: vec![Param::Required(Ident(String::from("x")))],
+                    body: vec![Stmt::Expr(Expr::Ident(Ident(String::from("x")), Span::default()), Span::default())],
+                    span: Span::default(),
+                }, Span::default()),
+            ],
+            program,
+        );
+    }
+
     #[test]
     fn test_call_expr() {
         let input = "add(1, 2 * 3, 4 + 5);";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![Stmt::Expr(Expr::Call {
-                func: Box::new(Expr::Ident(Ident(String::from("add")))),
+                func: Box::new(Expr::Ident(Ident(String::from("add")), Span::default())),
                 args: vec![
-                    Expr::Literal(Literal::Int(1)),
+                    Expr::Literal(Literal::Int(1), Span::default()),
                     Expr::Infix(
                         Infix::Multiply,
-                        Box::new(Expr::Literal(Literal::Int(2))),
-                        Box::new(Expr::Literal(Literal::Int(3))),
+                        Box::new(Expr::Literal(Literal::Int(2), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                        Span::default(),
                     ),
                     Expr::Infix(
                         Infix::Plus,
-                        Box::new(Expr::Literal(Literal::Int(4))),
-                        Box::new(Expr::Literal(Literal::Int(5))),
+                        Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Span::default(),
                     ),
                 ],
-            })],
+                named: vec![],
+                span: Span::default(),
+            }, Span::default())],
             program,
         );
     }
@@ -1384,10 +2004,12 @@ return 993322;
                     Infix::Multiply,
                     Box::new(Expr::Prefix(
                         Prefix::Minus,
-                        Box::new(Expr::Ident(Ident(String::from("a")))),
+                        Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Ident(Ident(String::from("b")))),
-                )),
+                    Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "!-a",
@@ -1395,9 +2017,11 @@ return 993322;
                     Prefix::Not,
                     Box::new(Expr::Prefix(
                         Prefix::Minus,
-                        Box::new(Expr::Ident(Ident(String::from("a")))),
+                        Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
+                        Span::default(),
                     )),
-                )),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "a + b + c",
@@ -1405,11 +2029,13 @@ return 993322;
                     Infix::Plus,
                     Box::new(Expr::Infix(
                         Infix::Plus,
-                        Box::new(Expr::Ident(Ident(String::from("a")))),
-                        Box::new(Expr::Ident(Ident(String::from("b")))),
+                        Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
+                        Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Ident(Ident(String::from("c")))),
-                )),
+                    Box::new(Expr::Ident(Ident(String::from("c")), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "a + b - c",
@@ -1417,11 +2043,13 @@ return 993322;
                     Infix::Minus,
                     Box::new(Expr::Infix(
                         Infix::Plus,
-                        Box::new(Expr::Ident(Ident(String::from("a")))),
-                        Box::new(Expr::Ident(Ident(String::from("b")))),
+                        Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
+                        Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Ident(Ident(String::from("c")))),
-                )),
+                    Box::new(Expr::Ident(Ident(String::from("c")), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "a * b * c",
@@ -1429,11 +2057,13 @@ return 993322;
                     Infix::Multiply,
                     Box::new(Expr::Infix(
                         Infix::Multiply,
-                        Box::new(Expr::Ident(Ident(String::from("a")))),
-                        Box::new(Expr::Ident(Ident(String::from("b")))),
+                        Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
+                        Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Ident(Ident(String::from("c")))),
-                )),
+                    Box::new(Expr::Ident(Ident(String::from("c")), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "a * b / c",
@@ -1441,23 +2071,27 @@ return 993322;
                     Infix::Divide,
                     Box::new(Expr::Infix(
                         Infix::Multiply,
-                        Box::new(Expr::Ident(Ident(String::from("a")))),
-                        Box::new(Expr::Ident(Ident(String::from("b")))),
+                        Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
+                        Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Ident(Ident(String::from("c")))),
-                )),
+                    Box::new(Expr::Ident(Ident(String::from("c")), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "a + b / c",
                 Stmt::Expr(Expr::Infix(
                     Infix::Plus,
-                    Box::new(Expr::Ident(Ident(String::from("a")))),
+                    Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
                     Box::new(Expr::Infix(
                         Infix::Divide,
-                        Box::new(Expr::Ident(Ident(String::from("b")))),
-                        Box::new(Expr::Ident(Ident(String::from("c")))),
+                        Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                        Box::new(Expr::Ident(Ident(String::from("c")), Span::default())),
+                        Span::default(),
                     )),
-                )),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "a + b * c + d / e - f",
@@ -1467,21 +2101,26 @@ return 993322;
                         Infix::Plus,
                         Box::new(Expr::Infix(
                             Infix::Plus,
-                            Box::new(Expr::Ident(Ident(String::from("a")))),
+                            Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
                             Box::new(Expr::Infix(
                                 Infix::Multiply,
-                                Box::new(Expr::Ident(Ident(String::from("b")))),
-                                Box::new(Expr::Ident(Ident(String::from("c")))),
+                                Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                                Box::new(Expr::Ident(Ident(String::from("c")), Span::default())),
+                                Span::default(),
                             )),
+                            Span::default(),
                         )),
                         Box::new(Expr::Infix(
                             Infix::Divide,
-                            Box::new(Expr::Ident(Ident(String::from("d")))),
-                            Box::new(Expr::Ident(Ident(String::from("e")))),
+                            Box::new(Expr::Ident(Ident(String::from("d")), Span::default())),
+                            Box::new(Expr::Ident(Ident(String::from("e")), Span::default())),
+                            Span::default(),
                         )),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Ident(Ident(String::from("f")))),
-                )),
+                    Box::new(Expr::Ident(Ident(String::from("f")), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 > 4 == 3 < 4",
@@ -1489,15 +2128,18 @@ return 993322;
                     Infix::Equal,
                     Box::new(Expr::Infix(
                         Infix::GT,
-                        Box::new(Expr::Literal(Literal::Int(5))),
-                        Box::new(Expr::Literal(Literal::Int(4))),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                        Span::default(),
                     )),
                     Box::new(Expr::Infix(
                         Infix::LT,
-                        Box::new(Expr::Literal(Literal::Int(3))),
-                        Box::new(Expr::Literal(Literal::Int(4))),
+                        Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                        Span::default(),
                     )),
-                )),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 < 4 != 3 > 4",
@@ -1505,15 +2147,18 @@ return 993322;
                     Infix::NotEqual,
                     Box::new(Expr::Infix(
                         Infix::LT,
-                        Box::new(Expr::Literal(Literal::Int(5))),
-                        Box::new(Expr::Literal(Literal::Int(4))),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                        Span::default(),
                     )),
                     Box::new(Expr::Infix(
                         Infix::GT,
-                        Box::new(Expr::Literal(Literal::Int(3))),
-                        Box::new(Expr::Literal(Literal::Int(4))),
+                        Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                        Span::default(),
                     )),
-                )),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 >= 4 == 3 <= 4",
@@ -1521,15 +2166,18 @@ return 993322;
                     Infix::Equal,
                     Box::new(Expr::Infix(
                         Infix::GTEQ,
-                        Box::new(Expr::Literal(Literal::Int(5))),
-                        Box::new(Expr::Literal(Literal::Int(4))),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                        Span::default(),
                     )),
                     Box::new(Expr::Infix(
                         Infix::LTEQ,
-                        Box::new(Expr::Literal(Literal::Int(3))),
-                        Box::new(Expr::Literal(Literal::Int(4))),
+                        Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                        Span::default(),
                     )),
-                )),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "5 <= 4 != 3 >= 4",
@@ -1537,15 +2185,18 @@ return 993322;
                     Infix::NotEqual,
                     Box::new(Expr::Infix(
                         Infix::LTEQ,
-                        Box::new(Expr::Literal(Literal::Int(5))),
-                        Box::new(Expr::Literal(Literal::Int(4))),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                        Span::default(),
                     )),
                     Box::new(Expr::Infix(
                         Infix::GTEQ,
-                        Box::new(Expr::Literal(Literal::Int(3))),
-                        Box::new(Expr::Literal(Literal::Int(4))),
+                        Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                        Span::default(),
                     )),
-                )),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "3 + 4 * 5 == 3 * 1 + 4 * 5",
@@ -1553,41 +2204,49 @@ return 993322;
                     Infix::Equal,
                     Box::new(Expr::Infix(
                         Infix::Plus,
-                        Box::new(Expr::Literal(Literal::Int(3))),
+                        Box::new(Expr::Literal(Literal::Int(3), Span::default())),
                         Box::new(Expr::Infix(
                             Infix::Multiply,
-                            Box::new(Expr::Literal(Literal::Int(4))),
-                            Box::new(Expr::Literal(Literal::Int(5))),
+                            Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                            Span::default(),
                         )),
+                        Span::default(),
                     )),
                     Box::new(Expr::Infix(
                         Infix::Plus,
                         Box::new(Expr::Infix(
                             Infix::Multiply,
-                            Box::new(Expr::Literal(Literal::Int(3))),
-                            Box::new(Expr::Literal(Literal::Int(1))),
+                            Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(1), Span::default())),
+                            Span::default(),
                         )),
                         Box::new(Expr::Infix(
                             Infix::Multiply,
-                            Box::new(Expr::Literal(Literal::Int(4))),
-                            Box::new(Expr::Literal(Literal::Int(5))),
+                            Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                            Span::default(),
                         )),
+                        Span::default(),
                     )),
-                )),
+                    Span::default(),
+                ), Span::default()),
             ),
-            ("true", Stmt::Expr(Expr::Literal(Literal::Bool(true)))),
-            ("false", Stmt::Expr(Expr::Literal(Literal::Bool(false)))),
+            ("true", Stmt::Expr(Expr::Literal(Literal::Bool(true), Span::default()), Span::default())),
+            ("false", Stmt::Expr(Expr::Literal(Literal::Bool(false), Span::default()), Span::default())),
             (
                 "3 > 5 == false",
                 Stmt::Expr(Expr::Infix(
                     Infix::Equal,
                     Box::new(Expr::Infix(
                         Infix::GT,
-                        Box::new(Expr::Literal(Literal::Int(3))),
-                        Box::new(Expr::Literal(Literal::Int(5))),
+                        Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Literal(Literal::Bool(false))),
-                )),
+                    Box::new(Expr::Literal(Literal::Bool(false), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "3 < 5 == true",
@@ -1595,11 +2254,13 @@ return 993322;
                     Infix::Equal,
                     Box::new(Expr::Infix(
                         Infix::LT,
-                        Box::new(Expr::Literal(Literal::Int(3))),
-                        Box::new(Expr::Literal(Literal::Int(5))),
+                        Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Literal(Literal::Bool(true))),
-                )),
+                    Box::new(Expr::Literal(Literal::Bool(true), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "1 + (2 + 3) + 4",
@@ -1607,15 +2268,18 @@ return 993322;
                     Infix::Plus,
                     Box::new(Expr::Infix(
                         Infix::Plus,
-                        Box::new(Expr::Literal(Literal::Int(1))),
+                        Box::new(Expr::Literal(Literal::Int(1), Span::default())),
                         Box::new(Expr::Infix(
                             Infix::Plus,
-                            Box::new(Expr::Literal(Literal::Int(2))),
-                            Box::new(Expr::Literal(Literal::Int(3))),
+                            Box::new(Expr::Literal(Literal::Int(2), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                            Span::default(),
                         )),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Literal(Literal::Int(4))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "(5 + 5) * 2",
@@ -1623,23 +2287,27 @@ return 993322;
                     Infix::Multiply,
                     Box::new(Expr::Infix(
                         Infix::Plus,
-                        Box::new(Expr::Literal(Literal::Int(5))),
-                        Box::new(Expr::Literal(Literal::Int(5))),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Literal(Literal::Int(2))),
-                )),
+                    Box::new(Expr::Literal(Literal::Int(2), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "2 / (5 + 5)",
                 Stmt::Expr(Expr::Infix(
                     Infix::Divide,
-                    Box::new(Expr::Literal(Literal::Int(2))),
+                    Box::new(Expr::Literal(Literal::Int(2), Span::default())),
                     Box::new(Expr::Infix(
                         Infix::Plus,
-                        Box::new(Expr::Literal(Literal::Int(5))),
-                        Box::new(Expr::Literal(Literal::Int(5))),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Span::default(),
                     )),
-                )),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "-(5 + 5)",
@@ -1647,10 +2315,12 @@ return 993322;
                     Prefix::Minus,
                     Box::new(Expr::Infix(
                         Infix::Plus,
-                        Box::new(Expr::Literal(Literal::Int(5))),
-                        Box::new(Expr::Literal(Literal::Int(5))),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                        Span::default(),
                     )),
-                )),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "!(true == true)",
@@ -1658,25 +2328,31 @@ return 993322;
                     Prefix::Not,
                     Box::new(Expr::Infix(
                         Infix::Equal,
-                        Box::new(Expr::Literal(Literal::Bool(true))),
-                        Box::new(Expr::Literal(Literal::Bool(true))),
+                        Box::new(Expr::Literal(Literal::Bool(true), Span::default())),
+                        Box::new(Expr::Literal(Literal::Bool(true), Span::default())),
+                        Span::default(),
                     )),
-                )),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "d + add(b * c)",
                 Stmt::Expr(Expr::Infix(
                     Infix::Plus,
-                    Box::new(Expr::Ident(Ident(String::from("d")))),
+                    Box::new(Expr::Ident(Ident(String::from("d")), Span::default())),
                     Box::new(Expr::Call {
-                        func: Box::new(Expr::Ident(Ident(String::from("add")))),
+                        func: Box::new(Expr::Ident(Ident(String::from("add")), Span::default())),
                         args: vec![Expr::Infix(
                             Infix::Multiply,
-                            Box::new(Expr::Ident(Ident(String::from("b")))),
-                            Box::new(Expr::Ident(Ident(String::from("c")))),
+                            Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                            Box::new(Expr::Ident(Ident(String::from("c")), Span::default())),
+                            Span::default(),
                         )],
+                        named: vec![],
+                        span: Span::default(),
                     }),
-                )),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "a + add(b * c) + d",
@@ -1684,77 +2360,96 @@ return 993322;
                     Infix::Plus,
                     Box::new(Expr::Infix(
                         Infix::Plus,
-                        Box::new(Expr::Ident(Ident(String::from("a")))),
+                        Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
                         Box::new(Expr::Call {
-                            func: Box::new(Expr::Ident(Ident(String::from("add")))),
+                            func: Box::new(Expr::Ident(Ident(String::from("add")), Span::default())),
                             args: vec![Expr::Infix(
                                 Infix::Multiply,
-                                Box::new(Expr::Ident(Ident(String::from("b")))),
-                                Box::new(Expr::Ident(Ident(String::from("c")))),
+                                Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                                Box::new(Expr::Ident(Ident(String::from("c")), Span::default())),
+                                Span::default(),
                             )],
+                            named: vec![],
+                            span: Span::default(),
                         }),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Ident(Ident(String::from("d")))),
-                )),
+                    Box::new(Expr::Ident(Ident(String::from("d")), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))",
                 Stmt::Expr(Expr::Call {
-                    func: Box::new(Expr::Ident(Ident(String::from("add")))),
+                    func: Box::new(Expr::Ident(Ident(String::from("add")), Span::default())),
                     args: vec![
-                        Expr::Ident(Ident(String::from("a"))),
-                        Expr::Ident(Ident(String::from("b"))),
-                        Expr::Literal(Literal::Int(1)),
+                        Expr::Ident(Ident(String::from("a")), Span::default()),
+                        Expr::Ident(Ident(String::from("b")), Span::default()),
+                        Expr::Literal(Literal::Int(1), Span::default()),
                         Expr::Infix(
                             Infix::Multiply,
-                            Box::new(Expr::Literal(Literal::Int(2))),
-                            Box::new(Expr::Literal(Literal::Int(3))),
+                            Box::new(Expr::Literal(Literal::Int(2), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(3), Span::default())),
+                            Span::default(),
                         ),
                         Expr::Infix(
                             Infix::Plus,
-                            Box::new(Expr::Literal(Literal::Int(4))),
-                            Box::new(Expr::Literal(Literal::Int(5))),
+                            Box::new(Expr::Literal(Literal::Int(4), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(5), Span::default())),
+                            Span::default(),
                         ),
                         Expr::Call {
-                            func: Box::new(Expr::Ident(Ident(String::from("add")))),
+                            func: Box::new(Expr::Ident(Ident(String::from("add")), Span::default())),
                             args: vec![
-                                Expr::Literal(Literal::Int(6)),
+                                Expr::Literal(Literal::Int(6), Span::default()),
                                 Expr::Infix(
                                     Infix::Multiply,
-                                    Box::new(Expr::Literal(Literal::Int(7))),
-                                    Box::new(Expr::Literal(Literal::Int(8))),
+                                    Box::new(Expr::Literal(Literal::Int(7), Span::default())),
+                                    Box::new(Expr::Literal(Literal::Int(8), Span::default())),
+                                    Span::default(),
                                 ),
                             ],
+                            named: vec![],
+                            span: Span::default(),
                         },
                     ],
-                }),
+                    named: vec![],
+                    span: Span::default(),
+                }, Span::default()),
             ),
             (
                 "add(a + b + c * d / f + g)",
                 Stmt::Expr(Expr::Call {
-                    func: Box::new(Expr::Ident(Ident(String::from("add")))),
+                    func: Box::new(Expr::Ident(Ident(String::from("add")), Span::default())),
                     args: vec![Expr::Infix(
                         Infix::Plus,
                         Box::new(Expr::Infix(
                             Infix::Plus,
                             Box::new(Expr::Infix(
                                 Infix::Plus,
-                                Box::new(Expr::Ident(Ident(String::from("a")))),
-                                Box::new(Expr::Ident(Ident(String::from("b")))),
+                                Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
+                                Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                                Span::default(),
                             )),
                             Box::new(Expr::Infix(
                                 Infix::Divide,
                                 Box::new(Expr::Infix(
                                     Infix::Multiply,
-                                    Box::new(Expr::Ident(Ident(String::from("c")))),
-                                    Box::new(Expr::Ident(Ident(String::from("d")))),
+                                    Box::new(Expr::Ident(Ident(String::from("c")), Span::default())),
+                                    Box::new(Expr::Ident(Ident(String::from("d")), Span::default())),
+                                    Span::default(),
                                 )),
-                                Box::new(Expr::Ident(Ident(String::from("f")))),
+                                Box::new(Expr::Ident(Ident(String::from("f")), Span::default())),
+                                Span::default(),
                             )),
+                            Span::default(),
                         )),
-                        Box::new(Expr::Ident(Ident(String::from("g")))),
+                        Box::new(Expr::Ident(Ident(String::from("g")), Span::default())),
+                        Span::default(),
                     )],
-                }),
+                    named: vec![],
+                    span: Span::default(),
+                }, Span::default()),
             ),
             (
                 "a * [1, 2, 3, 4][b * c] * d",
@@ -1762,60 +2457,71 @@ return 993322;
                     Infix::Multiply,
                     Box::new(Expr::Infix(
                         Infix::Multiply,
-                        Box::new(Expr::Ident(Ident(String::from("a")))),
+                        Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
                         Box::new(Expr::Index(
                             Box::new(Expr::Literal(Literal::Array(vec![
-                                Expr::Literal(Literal::Int(1)),
-                                Expr::Literal(Literal::Int(2)),
-                                Expr::Literal(Literal::Int(3)),
-                                Expr::Literal(Literal::Int(4)),
-                            ]))),
+                                Expr::Literal(Literal::Int(1), Span::default()),
+                                Expr::Literal(Literal::Int(2), Span::default()),
+                                Expr::Literal(Literal::Int(3), Span::default()),
+                                Expr::Literal(Literal::Int(4), Span::default()),
+                            ]), Span::default())),
                             Box::new(Expr::Infix(
                                 Infix::Multiply,
-                                Box::new(Expr::Ident(Ident(String::from("b")))),
-                                Box::new(Expr::Ident(Ident(String::from("c")))),
+                                Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                                Box::new(Expr::Ident(Ident(String::from("c")), Span::default())),
+                                Span::default(),
                             )),
+                            Span::default(),
                         )),
+                        Span::default(),
                     )),
-                    Box::new(Expr::Ident(Ident(String::from("d")))),
-                )),
+                    Box::new(Expr::Ident(Ident(String::from("d")), Span::default())),
+                    Span::default(),
+                ), Span::default()),
             ),
             (
                 "add(a * b[2], b[1], 2 * [1, 2][1])",
                 Stmt::Expr(Expr::Call {
-                    func: Box::new(Expr::Ident(Ident(String::from("add")))),
+                    func: Box::new(Expr::Ident(Ident(String::from("add")), Span::default())),
                     args: vec![
                         Expr::Infix(
                             Infix::Multiply,
-                            Box::new(Expr::Ident(Ident(String::from("a")))),
+                            Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
                             Box::new(Expr::Index(
-                                Box::new(Expr::Ident(Ident(String::from("b")))),
-                                Box::new(Expr::Literal(Literal::Int(2))),
+                                Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                                Box::new(Expr::Literal(Literal::Int(2), Span::default())),
+                                Span::default(),
                             )),
+                            Span::default(),
                         ),
                         Expr::Index(
-                            Box::new(Expr::Ident(Ident(String::from("b")))),
-                            Box::new(Expr::Literal(Literal::Int(1))),
+                            Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(1), Span::default())),
+                            Span::default(),
                         ),
                         Expr::Infix(
                             Infix::Multiply,
-                            Box::new(Expr::Literal(Literal::Int(2))),
+                            Box::new(Expr::Literal(Literal::Int(2), Span::default())),
                             Box::new(Expr::Index(
                                 Box::new(Expr::Literal(Literal::Array(vec![
-                                    Expr::Literal(Literal::Int(1)),
-                                    Expr::Literal(Literal::Int(2)),
-                                ]))),
-                                Box::new(Expr::Literal(Literal::Int(1))),
+                                    Expr::Literal(Literal::Int(1), Span::default()),
+                                    Expr::Literal(Literal::Int(2), Span::default()),
+                                ]), Span::default())),
+                                Box::new(Expr::Literal(Literal::Int(1), Span::default())),
+                                Span::default(),
                             )),
+                            Span::default(),
                         ),
                     ],
-                }),
+                    named: vec![],
+                    span: Span::default(),
+                }, Span::default()),
             ),
         ];
 
         for (input, expect) in tests {
             let mut parser = Parser::new(Lexer::new(input));
-            let program = parser.parse();
+            let program = strip_spans(parser.parse());
 
             check_parse_errors(&mut parser);
             assert_eq!(vec![expect], program);
@@ -1829,25 +2535,27 @@ return 993322;
         let input = "while (x < y) { b = b + 10; }";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![Stmt::Expr(Expr::While {
                 cond: Box::new(Expr::Infix(
                     Infix::LT,
-                    Box::new(Expr::Ident(Ident(String::from("x")))),
-                    Box::new(Expr::Ident(Ident(String::from("y")))),
+                    Box::new(Expr::Ident(Ident(String::from("x")), Span::default())),
+                    Box::new(Expr::Ident(Ident(String::from("y")), Span::default())),
+                    Span::default(),
                 )),
                 consequence: vec![Stmt::ReAssign(
                     Ident(String::from("b")),
                     Expr::Infix(
                         Infix::Plus,
-                        Box::new(Expr::Ident(Ident(String::from("b")))),
-                        Box::new(Expr::Literal(Literal::Int(10)))
-                    )
-                ),],
-            })],
+                        Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                        Box::new(Expr::Literal(Literal::Int(10), Span::default()))
+                    , Span::default())
+                , Span::default()),],
+                span: Span::default(),
+            }, Span::default())],
             program,
         );
     }
@@ -1857,36 +2565,39 @@ return 993322;
         let input = "while (x < y) { b = b + 10; if (b == 30) { break; } else { continue; } }";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![Stmt::Expr(Expr::While {
                 cond: Box::new(Expr::Infix(
                     Infix::LT,
-                    Box::new(Expr::Ident(Ident(String::from("x")))),
-                    Box::new(Expr::Ident(Ident(String::from("y")))),
+                    Box::new(Expr::Ident(Ident(String::from("x")), Span::default())),
+                    Box::new(Expr::Ident(Ident(String::from("y")), Span::default())),
+                    Span::default(),
                 )),
                 consequence: vec![
                     Stmt::ReAssign(
                         Ident(String::from("b")),
                         Expr::Infix(
                             Infix::Plus,
-                            Box::new(Expr::Ident(Ident(String::from("b")))),
-                            Box::new(Expr::Literal(Literal::Int(10)))
-                        )
-                    ),
+                            Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(10), Span::default()))
+                        , Span::default())
+                    , Span::default()),
                     Stmt::Expr(Expr::If {
                         cond: Box::new(Expr::Infix(
                             Infix::Equal,
-                            Box::new(Expr::Ident(Ident(String::from("b")))),
-                            Box::new(Expr::Literal(Literal::Int(30)))
-                        )),
-                        consequence: vec![Stmt::Break],
-                        alternative: Some(vec![Stmt::Continue]),
-                    }),
+                            Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(30), Span::default()))
+                        , Span::default())),
+                        consequence: vec![Stmt::Break(Span::default())],
+                        alternative: Some(vec![Stmt::Continue(Span::default())]),
+                        span: Span::default(),
+                    }, Span::default()),
                 ],
-            })],
+                span: Span::default(),
+            }, Span::default())],
             program,
         );
     }
@@ -1898,22 +2609,296 @@ x + y // just return x + y 即可
 }";
 
         let mut parser = Parser::new(Lexer::new(input));
-        let program = parser.parse();
+        let program = strip_spans(parser.parse());
 
         check_parse_errors(&mut parser);
         assert_eq!(
             vec![Stmt::Expr(Expr::Function {
-                params: vec![Ident(String::from("x")), Ident(String::from("y"))],
+                params: vec![Param::Required(Ident(String::from("x"))), Param::Required(Ident(String::from("y")))],
                 body: vec![Stmt::Expr(Expr::Infix(
                     Infix::Plus,
-                    Box::new(Expr::Ident(Ident(String::from("x")))),
-                    Box::new(Expr::Ident(Ident(String::from("y")))),
-                ))],
-            })],
+                    Box::new(Expr::Ident(Ident(String::from("x")), Span::default())),
+                    Box::new(Expr::Ident(Ident(String::from("y")), Span::default())),
+                    Span::default(),
+                ), Span::default())],
+                span: Span::default(),
+            }, Span::default())],
             program,
         );
     }
 
+    #[test]
+    fn test_logical_precedence() {
+        let input = "a || b && c == d";
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        check_parse_errors(&mut parser);
+        assert_eq!(
+            vec![Stmt::Expr(
+                Expr::Infix(
+                    Infix::Or,
+                    Box::new(Expr::Ident(Ident(String::from("a")), Span::default())),
+                    Box::new(Expr::Infix(
+                        Infix::And,
+                        Box::new(Expr::Ident(Ident(String::from("b")), Span::default())),
+                        Box::new(Expr::Infix(
+                            Infix::Equal,
+                            Box::new(Expr::Ident(Ident(String::from("c")), Span::default())),
+                            Box::new(Expr::Ident(Ident(String::from("d")), Span::default())),
+                            Span::default(),
+                        )),
+                        Span::default(),
+                    )),
+                    Span::default(),
+                ),
+                Span::default(),
+            )],
+            program,
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let input = "给 三体 以 1;\n广播(三体 + 2);";
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse();
+
+        check_parse_errors(&mut parser);
+
+        let span = program[1].span();
+        assert_eq!((span.line, span.column), (2, 1));
+
+        match &program[1] {
+            Stmt::Expr(Expr::Call { args, .. }, _) => {
+                let span = args[0].span();
+                assert_eq!((span.start, span.end, span.line, span.column), (25, 35, 2, 4));
+            }
+            _ => panic!("expect call expr"),
+        }
+    }
+
+    #[test]
+    fn test_error_span() {
+        let input = "给 三体 以 1;\n给 罗辑 ！ 2;";
+
+        let mut parser = Parser::new(Lexer::new(input));
+        parser.parse();
+
+        let errors = parser.get_errors();
+        let span = errors[0].span();
+        assert_eq!((span.line, span.column), (2, 6));
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let input = r#"
+给 三体 以 1;
+给 罗辑 ！ 2;
+给 章北海 以 ;
+广播(三体;
+给 叶文洁 以 4;
+"#;
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        let errors = parser.get_errors();
+        assert_eq!(
+            errors.iter().map(|e| e.span().line).collect::<Vec<_>>(),
+            vec![3, 4, 5]
+        );
+        assert_eq!(
+            program
+                .iter()
+                .filter_map(|stmt| match stmt {
+                    Stmt::Let(Ident(name), _, _, _) => Some(name.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            vec![String::from("三体"), String::from("叶文洁")]
+        );
+    }
+
+    #[test]
+    fn test_error_recovery_in_block() {
+        let input = r#"
+面壁 (x < 3) {
+    给 a 以 ;
+    思想钢印 b 以 2;
+}
+给 c 以 3;
+"#;
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        assert_eq!(parser.get_errors().len(), 1);
+        assert!(matches!(
+            &program[0],
+            Stmt::Expr(Expr::While { consequence, .. }, _)
+                if matches!(consequence.last(), Some(Stmt::Const(..)))
+        ));
+        assert!(matches!(program.last(), Some(Stmt::Let(..))));
+    }
+
+    #[test]
+    fn test_error_recovery_before_function() {
+        let input = "给 a 以 )\n法则 f(x) { 回归 x 前进 ) }\n法则 g() { 1 }";
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        let errors = parser.get_errors();
+        assert_eq!(
+            errors.iter().map(|e| (e.span().line, e.span().column)).collect::<Vec<_>>(),
+            vec![(1, 7), (2, 19)]
+        );
+        assert!(matches!(program.last(), Some(Stmt::Function(Ident(name), ..)) if name == "g"));
+    }
+
+    #[test]
+    fn test_error_recovery_no_panic() {
+        for input in ["1 +", ") + 1", "a.5 + 1", "fn(1) {}", "给 5 以 3; 给 a 以 1;"] {
+            let mut parser = Parser::new(Lexer::new(input));
+            parser.parse();
+            assert!(!parser.get_errors().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_integer_out_of_range() {
+        let input = "给 a 以 99999999999999999999;\n给 b 以 1;";
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        let errors = parser.get_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            ParseError::Lex { error: LexError::IntegerOutOfRange(_), span } if span.column == 7
+        ));
+        assert!(matches!(program.last(), Some(Stmt::Let(..))));
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        for input in ["给 a 以 \"3", "广播('三体", "[\"a\", \"b]"] {
+            let mut parser = Parser::new(Lexer::new(input));
+            parser.parse();
+
+            let errors = parser.get_errors();
+            assert!(
+                matches!(&errors[0], ParseError::Lex { error: LexError::UnterminatedString(_), .. }),
+                "{:?}",
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_template_expr() {
+        let input = "`纪年 ${危机纪年 + 1}: ${`${a}`}`";
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        check_parse_errors(&mut parser);
+        assert_eq!(
+            vec![Stmt::Expr(
+                Expr::Template(
+                    vec![
+                        TemplatePart::Text(String::from("纪年 ")),
+                        TemplatePart::Expr(Expr::Infix(
+                            Infix::Plus,
+                            Box::new(Expr::Ident(Ident(String::from("危机纪年")), Span::default())),
+                            Box::new(Expr::Literal(Literal::Int(1), Span::default())),
+                            Span::default(),
+                        )),
+                        TemplatePart::Text(String::from(": ")),
+                        TemplatePart::Expr(Expr::Template(
+                            vec![TemplatePart::Expr(Expr::Ident(Ident(String::from("a")), Span::default()))],
+                            Span::default(),
+                        )),
+                    ],
+                    Span::default(),
+                ),
+                Span::default(),
+            )],
+            program,
+        );
+    }
+
+    #[test]
+    fn test_template_expr_errors() {
+        let tests = vec![
+            ("给 a 以 `${}`;", Token::RBrace, 10),
+            ("给 a 以 `${1 2}`;", Token::Int(2), 12),
+            ("给 a 以\n`x ${1 +}`;", Token::RBrace, 9),
+        ];
+
+        for (input, got, column) in tests {
+            let mut parser = Parser::new(Lexer::new(input));
+            parser.parse();
+
+            let errors = parser.get_errors();
+            assert_eq!(errors.len(), 1, "{:?}", errors);
+            match &errors[0] {
+                ParseError::UnexpectedToken { got: token, span, .. } => {
+                    assert_eq!((token, span.column), (&got, column), "{}", input)
+                }
+                error => panic!("unexpected error {:?}", error),
+            }
+        }
+    }
+
+    #[test]
+    fn test_doc_comment() {
+        let input = r#"
+/// 宇宙的第一公理
+/// 生存是文明的第一需要
+给 公理 以 1;
+
+/* not a doc */
+思想钢印 /// dropped
+    水 = /// dropped too
+    '剧毒的';
+
+/// above a plain expr, dropped
+公理;
+给 无 以 2;
+
+/// 黑暗森林法则
+法则 猜疑链() { 1 }
+"#;
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = strip_spans(parser.parse());
+
+        check_parse_errors(&mut parser);
+        let docs: Vec<Option<String>> = program
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Let(_, _, doc, _)
+                | Stmt::Const(_, _, doc, _)
+                | Stmt::Function(_, _, _, doc, _) => Some(doc.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            docs,
+            vec![
+                Some(String::from("宇宙的第一公理\n生存是文明的第一需要")),
+                None,
+                None,
+                Some(String::from("黑暗森林法则")),
+            ]
+        );
+    }
+
     /// errors panic
 
     #[test]
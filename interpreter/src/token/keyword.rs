@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+
+use crate::token::Token;
+
+/// One spelling of a keyword
+/// (关键字表: lexer, repl 补全和文档共用同一张表)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyword {
+    pub word: &'static str,
+    pub token: Token,
+    /// the english keyword (or operator) this word stands for
+    pub english: &'static str,
+    /// what a chinese spelling means, empty for the english ones
+    pub meaning: &'static str,
+}
+
+const fn keyword(word: &'static str, token: Token, english: &'static str, meaning: &'static str) -> Keyword {
+    Keyword { word, token, english, meaning }
+}
+
+pub const KEYWORDS: &[Keyword] = &[
+    keyword("let", Token::Let, "let", ""),
+    keyword("给", Token::Let, "let", "give"),
+    keyword("以", Token::Assign, "=", "as"),
+    keyword("const", Token::Const, "const", ""),
+    keyword("思想钢印", Token::Const, "const", "thoughtcontrou"),
+    keyword("前进", Token::Plus, "+", "go forward"),
+    keyword("降维", Token::Minus, "-", "dimension reduction"),
+    keyword("前进以", Token::PlusAssign, "+=", "go forward as"),
+    keyword("降维以", Token::MinusAssign, "-=", "reduce dimension as"),
+    keyword("true", Token::Bool(true), "true", ""),
+    keyword("这是计划的一部分", Token::Bool(true), "true", "It's part of the plan."),
+    keyword("false", Token::Bool(false), "false", ""),
+    keyword("主不在乎", Token::Bool(false), "false", "The Lord doesn't care."),
+    keyword("null", Token::Null, "null", ""),
+    keyword("虚空", Token::Null, "null", "the void"),
+    keyword("fn", Token::Function, "fn", ""),
+    keyword("法则", Token::Function, "fn", "rule"),
+    keyword("if", Token::If, "if", ""),
+    keyword("如果", Token::If, "if", "if"),
+    keyword("else", Token::Else, "else", ""),
+    keyword("否则", Token::Else, "else", "otherwise"),
+    keyword("while", Token::While, "while", ""),
+    keyword("面壁", Token::While, "while", "face the wall"),
+    keyword("break", Token::Break, "break", ""),
+    keyword("破壁", Token::Break, "break", "break the wall"),
+    keyword("continue", Token::Continue, "continue", ""),
+    keyword("延绪", Token::Continue, "continue", "continue"),
+    keyword("return", Token::Return, "return", ""),
+    keyword("回归", Token::Return, "return", "the return movement"),
+    keyword("并且", Token::And, "&&", "and"),
+    keyword("或者", Token::Or, "||", "or"),
+];
+
+/// 繁體 spellings, the english ones come from KEYWORDS
+pub const TRADITIONAL_KEYWORDS: &[Keyword] = &[
+    keyword("給", Token::Let, "let", "give"),
+    keyword("以", Token::Assign, "=", "as"),
+    keyword("思想鋼印", Token::Const, "const", "thoughtcontrou"),
+    keyword("前進", Token::Plus, "+", "go forward"),
+    keyword("降維", Token::Minus, "-", "dimension reduction"),
+    keyword("前進以", Token::PlusAssign, "+=", "go forward as"),
+    keyword("降維以", Token::MinusAssign, "-=", "reduce dimension as"),
+    keyword("這是計劃的一部分", Token::Bool(true), "true", "It's part of the plan."),
+    keyword("主不在乎", Token::Bool(false), "false", "The Lord doesn't care."),
+    keyword("虛空", Token::Null, "null", "the void"),
+    keyword("法則", Token::Function, "fn", "rule"),
+    keyword("如果", Token::If, "if", "if"),
+    keyword("否則", Token::Else, "else", "otherwise"),
+    keyword("面壁", Token::While, "while", "face the wall"),
+    keyword("破壁", Token::Break, "break", "break the wall"),
+    keyword("延緒", Token::Continue, "continue", "continue"),
+    keyword("回歸", Token::Return, "return", "the return movement"),
+    keyword("並且", Token::And, "&&", "and"),
+    keyword("或者", Token::Or, "||", "or"),
+];
+
+/// (alias, builtin) pairs, builtins are registered under their simplified names
+pub const TRADITIONAL_BUILTINS: &[(&str, &str)] = &[
+    ("廣播", "广播"),
+    ("毀滅", "毁灭"),
+    ("沒關係的都一樣", "没关系的都一样"),
+    ("中文數字", "中文数字"),
+];
+
+pub const ENGLISH_BUILTINS: &[(&str, &str)] = &[
+    ("print", "广播"),
+    ("sleep", "冬眠"),
+    ("clear", "二向箔清理"),
+    ("exit", "毁灭"),
+    ("deep_equal", "没关系的都一样"),
+    ("chinese_numeral", "中文数字"),
+    ("sophon", "智子工程"),
+    ("threading", "程心"),
+];
+
+/// keywords spelled differently from their english form, as shown in the docs
+pub fn aliases() -> impl Iterator<Item = &'static Keyword> {
+    KEYWORDS.iter().filter(|keyword| keyword.word != keyword.english)
+}
+
+/// The words a lexer reads as keywords, plus extra names for builtin functions
+/// (关键字语言包: 简体, 繁體, English, 或者用户自定义)
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeywordSet {
+    keywords: HashMap<String, Token>,
+    builtins: Vec<(String, String)>,
+}
+
+impl Default for KeywordSet {
+    fn default() -> Self {
+        KeywordSet::simplified()
+    }
+}
+
+impl KeywordSet {
+    /// no keyword at all, every word is an ident
+    pub fn empty() -> Self {
+        KeywordSet {
+            keywords: HashMap::new(),
+            builtins: vec![],
+        }
+    }
+
+    pub fn english() -> Self {
+        let mut set = KeywordSet::empty();
+        set.extend(KEYWORDS.iter().filter(|keyword| keyword.word == keyword.english));
+        set.extend_builtins(ENGLISH_BUILTINS);
+        set
+    }
+
+    /// english and 简体 spellings, the default
+    pub fn simplified() -> Self {
+        let mut set = KeywordSet::empty();
+        set.extend(KEYWORDS.iter());
+        set
+    }
+
+    /// english and 繁體 spellings
+    pub fn traditional() -> Self {
+        let mut set = KeywordSet::empty();
+        set.extend(KEYWORDS.iter().filter(|keyword| keyword.word == keyword.english));
+        set.extend(TRADITIONAL_KEYWORDS.iter());
+        set.extend_builtins(TRADITIONAL_BUILTINS);
+        set
+    }
+
+    /// a built-in pack by name, e.g. from the command line
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "simplified" | "zh-hans" | "zh" => Some(KeywordSet::simplified()),
+            "traditional" | "zh-hant" => Some(KeywordSet::traditional()),
+            "english" | "en" => Some(KeywordSet::english()),
+            _ => None,
+        }
+    }
+
+    /// make word stand for an english keyword or operator, e.g. ("讓", "let")
+    pub fn insert(&mut self, word: &str, english: &str) -> Result<(), String> {
+        match KEYWORDS.iter().find(|keyword| keyword.english == english) {
+            Some(keyword) => {
+                self.keywords.insert(word.to_string(), keyword.token.clone());
+                Ok(())
+            }
+            None => Err(format!("unknown keyword `{}` for `{}`", english, word)),
+        }
+    }
+
+    /// stop reading word as a keyword
+    pub fn remove(&mut self, word: &str) {
+        self.keywords.remove(word);
+    }
+
+    /// make alias another name of a builtin function, e.g. ("print", "广播")
+    pub fn alias_builtin(&mut self, alias: &str, builtin: &str) {
+        self.builtins.retain(|(other, _)| other != alias);
+        self.builtins.push((alias.to_string(), builtin.to_string()));
+    }
+
+    /// the token a word stands for, if it is a keyword
+    pub fn lookup(&self, word: &str) -> Option<Token> {
+        self.keywords.get(word).cloned()
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.keywords.keys().map(|word| word.as_str())
+    }
+
+    /// (alias, builtin) pairs
+    pub fn builtin_aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.builtins.iter().map(|(alias, builtin)| (alias.as_str(), builtin.as_str()))
+    }
+
+    fn extend<'a>(&mut self, keywords: impl Iterator<Item = &'a Keyword>) {
+        for keyword in keywords {
+            self.keywords.insert(keyword.word.to_string(), keyword.token.clone());
+        }
+    }
+
+    fn extend_builtins(&mut self, builtins: &[(&str, &str)]) {
+        for (alias, builtin) in builtins {
+            self.alias_builtin(alias, builtin);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simplified() {
+        let set = KeywordSet::simplified();
+        assert_eq!(set.lookup("给"), Some(Token::Let));
+        assert_eq!(set.lookup("let"), Some(Token::Let));
+        assert_eq!(set.lookup("如果"), Some(Token::If));
+        assert_eq!(set.lookup("虚空"), Some(Token::Null));
+        assert_eq!(set.lookup("三体"), None);
+        assert_eq!(set, KeywordSet::default());
+    }
+
+    #[test]
+    fn test_traditional() {
+        let set = KeywordSet::traditional();
+        assert_eq!(set.lookup("給"), Some(Token::Let));
+        assert_eq!(set.lookup("思想鋼印"), Some(Token::Const));
+        assert_eq!(set.lookup("const"), Some(Token::Const));
+        assert_eq!(set.lookup("思想钢印"), None);
+        assert!(set.builtin_aliases().any(|alias| alias == ("廣播", "广播")));
+    }
+
+    #[test]
+    fn test_english() {
+        let set = KeywordSet::english();
+        assert_eq!(set.lookup("while"), Some(Token::While));
+        assert_eq!(set.lookup("面壁"), None);
+        assert_eq!(set.lookup("以"), None);
+        assert!(set.builtin_aliases().any(|alias| alias == ("print", "广播")));
+    }
+
+    #[test]
+    fn test_custom() {
+        let mut set = KeywordSet::english();
+        assert_eq!(set.insert("讓", "let"), Ok(()));
+        assert_eq!(set.insert("加", "+"), Ok(()));
+        assert_eq!(
+            set.insert("甲", "loop"),
+            Err(String::from("unknown keyword `loop` for `甲`"))
+        );
+        set.remove("let");
+        set.alias_builtin("say", "广播");
+
+        assert_eq!(set.lookup("讓"), Some(Token::Let));
+        assert_eq!(set.lookup("加"), Some(Token::Plus));
+        assert_eq!(set.lookup("let"), None);
+        assert!(set.builtin_aliases().any(|alias| alias == ("say", "广播")));
+    }
+
+    #[test]
+    fn test_every_keyword_has_a_traditional_spelling() {
+        for keyword in aliases() {
+            assert!(
+                TRADITIONAL_KEYWORDS.iter().any(|other| other.token == keyword.token),
+                "{} has no traditional spelling",
+                keyword.word
+            );
+        }
+    }
+
+    #[test]
+    fn test_words_are_unique() {
+        for (i, keyword) in KEYWORDS.iter().enumerate() {
+            assert!(
+                KEYWORDS[i + 1..].iter().all(|other| other.word != keyword.word),
+                "{} is listed twice",
+                keyword.word
+            );
+        }
+    }
+}
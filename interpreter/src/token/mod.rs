@@ -1,3 +1,5 @@
+pub mod keyword;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Illegal,
@@ -6,6 +8,7 @@ pub enum Token {
     // identifiers + literals
     Ident(String),
     Int(i64),
+    Float(f64),
 
     // operations
     Assign,
@@ -14,6 +17,21 @@ pub enum Token {
     Bang,
     Asterisk,
     Slash,
+    Percent,    // %
+    Power,      // **
+    BitAnd,     // &
+    BitOr,      // |
+    BitXor,     // ^
+    ShiftLeft,  // <<
+    ShiftRight, // >>
+    Tilde,      // ~
+
+    // compound assign
+    PlusAssign,     // +=
+    MinusAssign,    // -=
+    AsteriskAssign, // *=
+    SlashAssign,    // /=
+    PercentAssign,  // %=
 
     // condition
     While,
@@ -23,6 +41,10 @@ pub enum Token {
     LTEQ,
     GTEQ,
 
+    // logical
+    And, // &&
+    Or,  // ||
+
     // Delimiters
     Comma,
     Semicolon,
@@ -32,6 +54,7 @@ pub enum Token {
     LBrace, // {
     RBrace, // }
     Dot,
+    Ellipsis, // ...
 
     // Keywords
     Function,
@@ -44,11 +67,13 @@ pub enum Token {
     Break,
     Continue,
     Return,
+    Null,
 
     Equal,
     NotEqual,
 
     String(String),
+    Template(Vec<TemplateSegment>), // `text ${expr}`
     Bool(bool),
 
     LBracket,
@@ -57,14 +82,210 @@ pub enum Token {
     Colon,
 
     Blank,
+
+    DocComment(String), // /// text
+
+    // the lexer could not make a valid token here
+    Error(LexError),
+}
+
+/// A piece of a template string: text with escapes resolved,
+/// or the source of an embedded `${...}` expression and where it sits
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateSegment {
+    Text(String),
+    Code(String, Span),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexError {
+    IntegerOutOfRange(String),
+    /// the input ended before the closing quote
+    UnterminatedString(char),
+    InvalidEscape(String),
+    InvalidUnicodeEscape(String),
+    /// the input ended inside a /* block comment
+    UnterminatedComment,
+    /// full-width punctuation or digit in strict mode
+    FullWidth(char),
+    /// a word of chinese numeral chars which is not a well formed number
+    InvalidNumeral(String),
+    /// a digit the radix of the literal has no place for, like the 2 in 0b102
+    InvalidDigit(String, char),
+    /// a `_` not followed by a digit, like 1_
+    TrailingUnderscore(String),
+    /// a radix prefix without digits, like 0x
+    MissingDigits(String),
+}
+
+/// what a literal with a radix prefix is called in messages
+pub fn radix_name(literal: &str) -> &'static str {
+    match literal.get(..2) {
+        Some("0x") => "hexadecimal",
+        Some("0o") => "octal",
+        Some("0b") => "binary",
+        _ => "decimal",
+    }
+}
+
+impl std::fmt::Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LexError::IntegerOutOfRange(literal) => {
+                write!(f, "integer literal `{}` is out of range", literal)
+            }
+            LexError::UnterminatedString(_) => write!(f, "unterminated string"),
+            LexError::InvalidEscape(escape) => write!(f, "invalid escape `{}` in string", escape),
+            LexError::InvalidUnicodeEscape(escape) => {
+                write!(f, "invalid unicode escape `{}` in string", escape)
+            }
+            LexError::UnterminatedComment => write!(f, "unterminated block comment"),
+            LexError::FullWidth(c) => write!(f, "full-width `{}` is not allowed in strict mode", c),
+            LexError::InvalidNumeral(word) => write!(f, "invalid chinese numeral `{}`", word),
+            LexError::InvalidDigit(literal, digit) => {
+                write!(f, "invalid digit `{}` in {} literal `{}`", digit, radix_name(literal), literal)
+            }
+            LexError::TrailingUnderscore(literal) => {
+                write!(f, "number literal `{}` ends with `_`", literal)
+            }
+            LexError::MissingDigits(literal) => {
+                write!(f, "missing digits after `{}`", &literal[..2])
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Illegal => write!(f, "illegal character"),
+            Token::Eof => write!(f, "end of input"),
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Int(value) => write!(f, "{}", value),
+            Token::Float(value) => write!(f, "{:?}", value),
+            Token::Assign => write!(f, "="),
+            Token::Plus => write!(f, "+"),
+            Token::Minus => write!(f, "-"),
+            Token::Bang => write!(f, "!"),
+            Token::Asterisk => write!(f, "*"),
+            Token::Slash => write!(f, "/"),
+            Token::Percent => write!(f, "%"),
+            Token::Power => write!(f, "**"),
+            Token::BitAnd => write!(f, "&"),
+            Token::BitOr => write!(f, "|"),
+            Token::BitXor => write!(f, "^"),
+            Token::ShiftLeft => write!(f, "<<"),
+            Token::ShiftRight => write!(f, ">>"),
+            Token::Tilde => write!(f, "~"),
+            Token::PlusAssign => write!(f, "+="),
+            Token::MinusAssign => write!(f, "-="),
+            Token::AsteriskAssign => write!(f, "*="),
+            Token::SlashAssign => write!(f, "/="),
+            Token::PercentAssign => write!(f, "%="),
+            Token::While => write!(f, "while"),
+            Token::LT => write!(f, "<"),
+            Token::GT => write!(f, ">"),
+            Token::LTEQ => write!(f, "<="),
+            Token::GTEQ => write!(f, ">="),
+            Token::And => write!(f, "&&"),
+            Token::Or => write!(f, "||"),
+            Token::Comma => write!(f, ","),
+            Token::Semicolon => write!(f, ";"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::LBrace => write!(f, "{{"),
+            Token::RBrace => write!(f, "}}"),
+            Token::Dot => write!(f, "."),
+            Token::Ellipsis => write!(f, "..."),
+            Token::Function => write!(f, "fn"),
+            Token::Let => write!(f, "let"),
+            Token::Const => write!(f, "const"),
+            Token::True => write!(f, "true"),
+            Token::False => write!(f, "false"),
+            Token::If => write!(f, "if"),
+            Token::Else => write!(f, "else"),
+            Token::Break => write!(f, "break"),
+            Token::Continue => write!(f, "continue"),
+            Token::Return => write!(f, "return"),
+            Token::Null => write!(f, "null"),
+            Token::Equal => write!(f, "=="),
+            Token::NotEqual => write!(f, "!="),
+            Token::String(value) => write!(f, "{:?}", value),
+            Token::Template(segments) => {
+                write!(f, "`")?;
+                for segment in segments {
+                    match segment {
+                        TemplateSegment::Text(text) => write!(f, "{}", text)?,
+                        TemplateSegment::Code(code, _) => write!(f, "${{{}}}", code)?,
+                    }
+                }
+                write!(f, "`")
+            }
+            Token::Bool(value) => write!(f, "{}", value),
+            Token::LBracket => write!(f, "["),
+            Token::RBracket => write!(f, "]"),
+            Token::Colon => write!(f, ":"),
+            Token::Blank => write!(f, "blank line"),
+            Token::DocComment(text) => write!(f, "/// {}", text),
+            Token::Error(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// Where a token (or an ast node) lives in the source.
+/// start/end are byte offsets, line/column (1-based, column counted in chars) point at start.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Span { start, end, line, column }
+    }
+
+    /// the span covering from self up to the end of other
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: std::cmp::max(self.end, other.end),
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
+/// A token with the exact source text it was read from, and the whitespace and comments
+/// (trivia) in front of it. Joining trivia + text of every token gives back the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LosslessToken {
+    pub trivia: String,
+    pub token: Token,
+    pub text: String,
 }
 
 
 #[cfg(test)]
 mod tests {
+    use super::Span;
     use super::Token;
     #[test]
     fn test_token_able_to_eq() {
         assert_eq!(Token::Let, Token::Let)
     }
+
+    #[test]
+    fn test_span_to() {
+        let span = Span::new(2, 4, 1, 3).to(Span::new(7, 9, 1, 8));
+        assert_eq!((span.start, span.end, span.line, span.column), (2, 9, 1, 3));
+    }
 }
\ No newline at end of file
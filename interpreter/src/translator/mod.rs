@@ -0,0 +1,210 @@
+use crate::lexer::Lexer;
+use crate::token::keyword::{
+    Keyword, KeywordSet, ENGLISH_BUILTINS, KEYWORDS, TRADITIONAL_BUILTINS, TRADITIONAL_KEYWORDS,
+};
+use crate::token::{TemplateSegment, Token};
+
+/// Which spelling of keywords and builtin names a script is translated to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Spelling {
+    Simplified,
+    Traditional,
+    English,
+}
+
+impl Spelling {
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "zh" | "zh-hans" | "simplified" => Some(Spelling::Simplified),
+            "zh-hant" | "traditional" => Some(Spelling::Traditional),
+            "en" | "english" => Some(Spelling::English),
+            _ => None,
+        }
+    }
+
+    fn keywords(&self) -> &'static [Keyword] {
+        match self {
+            Spelling::Simplified => KEYWORDS,
+            Spelling::Traditional => TRADITIONAL_KEYWORDS,
+            Spelling::English => &[],
+        }
+    }
+
+    fn builtins(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Spelling::Simplified => &[],
+            Spelling::Traditional => TRADITIONAL_BUILTINS,
+            Spelling::English => ENGLISH_BUILTINS,
+        }
+    }
+
+    /// how this spelling writes the keyword whose english form is given
+    fn keyword(&self, english: &'static str) -> &'static str {
+        self.keywords()
+            .iter()
+            .find(|keyword| keyword.english == english && keyword.word != english)
+            .map_or(english, |keyword| keyword.word)
+    }
+
+    /// how this spelling names a builtin, given by its registered name
+    fn builtin<'a>(&self, builtin: &'a str) -> &'a str {
+        self.builtins()
+            .iter()
+            .find(|(_, other)| *other == builtin)
+            .map_or(builtin, |(alias, _)| alias)
+    }
+}
+
+/// Rewrite keywords, operator words and builtin names of a script into another spelling.
+/// Only those words change, comments, strings and formatting are kept as they are.
+pub fn translate(source: &str, to: Spelling) -> String {
+    let mut lexer = Lexer::with_keywords(source, every_spelling());
+    let mut result = String::with_capacity(source.len());
+    let mut offset = 0;
+
+    for token in lexer.lossless_tokens() {
+        result.push_str(&token.trivia);
+        offset += token.trivia.len();
+        match (&token.token, translate_word(&token.token, &token.text, to)) {
+            (Token::Template(segments), _) => {
+                result.push_str(&translate_template(source, offset, &token.text, segments, to))
+            }
+            (_, Some(word)) => result.push_str(word),
+            (_, None) => result.push_str(&token.text),
+        }
+        offset += token.text.len();
+    }
+
+    result
+}
+
+/// a template literal read from source at start, with the code of each `${...}` translated
+fn translate_template(
+    source: &str,
+    start: usize,
+    text: &str,
+    segments: &[TemplateSegment],
+    to: Spelling,
+) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut copied = start;
+
+    for segment in segments {
+        if let TemplateSegment::Code(code, span) = segment {
+            result.push_str(&source[copied..span.start]);
+            result.push_str(&translate(code, to));
+            copied = span.end;
+        }
+    }
+    result.push_str(&source[copied..start + text.len()]);
+
+    result
+}
+
+fn translate_word<'a>(token: &Token, text: &'a str, to: Spelling) -> Option<&'a str> {
+    // symbols like `=` or `&&` stay as they are, only words are translated
+    if !text.chars().next().is_some_and(char::is_alphabetic) {
+        return None;
+    }
+
+    if let Token::Ident(name) = token {
+        let builtin = builtin_name(name)?;
+        return Some(to.builtin(builtin));
+    }
+
+    let english = KEYWORDS
+        .iter()
+        .chain(TRADITIONAL_KEYWORDS)
+        .find(|keyword| keyword.word == text)?
+        .english;
+    Some(to.keyword(english))
+}
+
+/// the registered name of a builtin, from any of its spellings
+fn builtin_name(name: &str) -> Option<&'static str> {
+    ENGLISH_BUILTINS
+        .iter()
+        .chain(TRADITIONAL_BUILTINS)
+        .find(|(alias, builtin)| *alias == name || *builtin == name)
+        .map(|(_, builtin)| *builtin)
+}
+
+/// a keyword set reading every spelling, so that any script can be translated
+fn every_spelling() -> KeywordSet {
+    let mut keywords = KeywordSet::empty();
+    for keyword in KEYWORDS.iter().chain(TRADITIONAL_KEYWORDS) {
+        let _ = keywords.insert(keyword.word, keyword.english);
+    }
+    keywords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_to_chinese() {
+        let input = r#"// count down
+let count = 3;
+while (count > 0) {
+    print("let " + count); // keep the string
+    count = count - 1;
+    if (count == 1) { break; } else { continue; }
+}
+return null;
+"#;
+
+        assert_eq!(
+            translate(input, Spelling::Simplified),
+            r#"// count down
+给 count = 3;
+面壁 (count > 0) {
+    广播("let " + count); // keep the string
+    count = count - 1;
+    如果 (count == 1) { 破壁; } 否则 { 延绪; }
+}
+回归 虚空;
+"#
+        );
+    }
+
+    #[test]
+    fn test_translate_template() {
+        let input = "给 a 以 `这是计划的一部分 ${ 这是计划的一部分 } ${ `${虚空}` }`;";
+
+        assert_eq!(
+            translate(input, Spelling::English),
+            "let a = `这是计划的一部分 ${ true } ${ `${null}` }`;"
+        );
+    }
+
+    #[test]
+    fn test_translate_to_english() {
+        let input = "给 自然选择 以 0;\n\n自然选择 前进以 4;\n思想钢印 水 = '剧毒的' ;\n广播(这是计划的一部分 并且 自然选择 降维 1)";
+
+        assert_eq!(
+            translate(input, Spelling::English),
+            "let 自然选择 = 0;\n\n自然选择 += 4;\nconst 水 = '剧毒的' ;\nprint(true && 自然选择 - 1)"
+        );
+    }
+
+    #[test]
+    fn test_translate_to_traditional() {
+        assert_eq!(
+            translate("给 水 以 法则() { 广播(\"思想钢印\") };", Spelling::Traditional),
+            "給 水 以 法則() { 廣播(\"思想钢印\") };"
+        );
+        assert_eq!(
+            translate("給 水 以 1; 廣播(水)", Spelling::Simplified),
+            "给 水 以 1; 广播(水)"
+        );
+    }
+
+    #[test]
+    fn test_translate_round_trip() {
+        let input = "给 a 以 [1, 2];\na[0] 前进以 1; // 给 stays\n\n\n\t广播(a)  ";
+        let english = translate(input, Spelling::English);
+        assert_eq!(english, "let a = [1, 2];\na[0] += 1; // 给 stays\n\n\n\tprint(a)  ");
+        assert_eq!(translate(&english, Spelling::English), english);
+    }
+}
@@ -3,18 +3,63 @@ extern crate rustyline;
 #[cfg(feature = "repl")]
 extern crate rustyline_derive;
 
+use three_body_interpreter::diagnostics::Diagnostic;
+use three_body_interpreter::evaluator::builtins::new_builtins_with;
 use three_body_interpreter::lexer::Lexer;
 use three_body_interpreter::parser::ParseError;
 use three_body_interpreter::parser::Parser;
-use three_body_interpreter::token::Token;
+use three_body_interpreter::token::keyword::KeywordSet;
+use three_body_interpreter::token::{LexError, Token};
 
-#[derive(rustyline_derive::Helper, rustyline_derive::Hinter, rustyline_derive::Highlighter, rustyline_derive::Completer)]
+#[derive(rustyline_derive::Helper, rustyline_derive::Hinter, rustyline_derive::Highlighter)]
 pub struct Helper {
+    keywords: KeywordSet,
+    strict: bool,
+    /// keywords and builtin names offered on tab
+    words: Vec<String>,
 }
 
 impl Helper {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(keywords: &KeywordSet, strict: bool) -> Self {
+        let mut words: Vec<String> = keywords.words().map(|word| word.to_string()).collect();
+        words.extend(new_builtins_with(keywords).into_keys());
+        words.sort();
+        words.dedup();
+        Self {
+            keywords: keywords.clone(),
+            strict,
+            words,
+        }
+    }
+
+    fn candidates(&self, prefix: &str) -> Vec<String> {
+        if prefix.is_empty() {
+            return vec![];
+        }
+        self.words
+            .iter()
+            .filter(|word| word.starts_with(prefix) && word.as_str() != prefix)
+            .cloned()
+            .collect()
+    }
+}
+
+impl rustyline::completion::Completer for Helper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .char_indices()
+            .rev()
+            .take_while(|(_, ch)| ch.is_alphanumeric() || *ch == '_')
+            .last()
+            .map_or(pos, |(i, _)| i);
+        Ok((start, self.candidates(&line[start..pos])))
     }
 }
 
@@ -23,7 +68,9 @@ impl rustyline::validate::Validator for Helper {
         &self,
         ctx: &mut rustyline::validate::ValidationContext,
     ) -> rustyline::Result<rustyline::validate::ValidationResult> {
-        let mut parser = Parser::new(Lexer::new(ctx.input()));
+        let mut lexer = Lexer::with_keywords(ctx.input(), self.keywords.clone());
+        lexer.set_strict(self.strict);
+        let mut parser = Parser::new(lexer);
         let _ = parser.parse();
         let errors = parser.get_errors();
 
@@ -31,10 +78,17 @@ impl rustyline::validate::Validator for Helper {
             0 => rustyline::validate::ValidationResult::Valid(None),
             _ => match &errors[0] {
                 ParseError::UnexpectedToken {
-                    want: _,
                     got: Token::Eof,
+                    ..
+                }
+                | ParseError::Lex {
+                    error: LexError::UnterminatedString(_) | LexError::UnterminatedComment,
+                    ..
                 } => rustyline::validate::ValidationResult::Incomplete,
-                x => rustyline::validate::ValidationResult::Invalid(Some(format!("{:?}???", x))),
+                x => rustyline::validate::ValidationResult::Invalid(Some(format!(
+                    "\n{}",
+                    Diagnostic::from(x).render_plain("<repl>", ctx.input())
+                ))),
             },
         })
     }
@@ -43,3 +97,22 @@ impl rustyline::validate::Validator for Helper {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let helper = Helper::new(&KeywordSet::default(), false);
+        assert!(helper.candidates("思想").contains(&String::from("思想钢印")));
+        assert!(helper.candidates("广").contains(&String::from("广播")));
+        assert!(helper.candidates("如").contains(&String::from("如果")));
+        assert_eq!(helper.candidates("给"), Vec::<String>::new());
+        assert_eq!(helper.candidates(""), Vec::<String>::new());
+
+        let helper = Helper::new(&KeywordSet::english(), false);
+        assert!(helper.candidates("pr").contains(&String::from("print")));
+        assert!(helper.candidates("思想").is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+use three_body_interpreter::token::keyword::KeywordSet;
+
+/// A keyword pack is either a built-in name (simplified, traditional, english)
+/// or a TOML / JSON file like
+///
+/// ```toml
+/// base = "english"
+/// remove = ["fn"]
+///
+/// [keywords]
+/// "讓" = "let"
+///
+/// [builtins]
+/// "say" = "广播"
+/// ```
+pub fn load(pack: &str) -> Result<KeywordSet, String> {
+    if let Some(keywords) = KeywordSet::by_name(pack) {
+        return Ok(keywords);
+    }
+
+    let path = Path::new(pack);
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("can not read keyword pack `{}`: {}", pack, err))?;
+    let value = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str::<Value>(&text).map_err(|err| err.to_string()),
+        _ => toml::from_str::<Value>(&text).map_err(|err| err.to_string()),
+    }
+    .map_err(|err| format!("invalid keyword pack `{}`: {}", pack, err))?;
+
+    parse(&value).map_err(|err| format!("invalid keyword pack `{}`: {}", pack, err))
+}
+
+fn parse(value: &Value) -> Result<KeywordSet, String> {
+    let mut keywords = match value.get("base") {
+        None => KeywordSet::default(),
+        Some(Value::String(base)) => {
+            KeywordSet::by_name(base).ok_or(format!("unknown base pack `{}`", base))?
+        }
+        Some(_) => return Err(String::from("`base` should be a string")),
+    };
+
+    for word in strings(value, "remove")? {
+        keywords.remove(word);
+    }
+    for (word, english) in pairs(value, "keywords")? {
+        keywords.insert(word, english)?;
+    }
+    for (alias, builtin) in pairs(value, "builtins")? {
+        keywords.alias_builtin(alias, builtin);
+    }
+
+    Ok(keywords)
+}
+
+fn strings<'a>(value: &'a Value, key: &str) -> Result<Vec<&'a str>, String> {
+    match value.get(key) {
+        None => Ok(vec![]),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| item.as_str().ok_or(format!("`{}` should only hold strings", key)))
+            .collect(),
+        Some(_) => Err(format!("`{}` should be a list of strings", key)),
+    }
+}
+
+fn pairs<'a>(value: &'a Value, key: &str) -> Result<Vec<(&'a str, &'a str)>, String> {
+    match value.get(key) {
+        None => Ok(vec![]),
+        Some(Value::Object(table)) => table
+            .iter()
+            .map(|(word, target)| match target.as_str() {
+                Some(target) => Ok((word.as_str(), target)),
+                None => Err(format!("`{}.{}` should be a string", key, word)),
+            })
+            .collect(),
+        Some(_) => Err(format!("`{}` should be a table of strings", key)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use three_body_interpreter::token::Token;
+
+    #[test]
+    fn test_parse_toml() {
+        let value = toml::from_str::<Value>(
+            r#"
+base = "english"
+remove = ["fn"]
+
+[keywords]
+"讓" = "let"
+
+[builtins]
+"say" = "广播"
+"#,
+        )
+        .unwrap();
+        let keywords = parse(&value).unwrap();
+
+        assert_eq!(keywords.lookup("讓"), Some(Token::Let));
+        assert_eq!(keywords.lookup("let"), Some(Token::Let));
+        assert_eq!(keywords.lookup("fn"), None);
+        assert_eq!(keywords.lookup("给"), None);
+        assert!(keywords.builtin_aliases().any(|alias| alias == ("say", "广播")));
+    }
+
+    #[test]
+    fn test_parse_json() {
+        let value = serde_json::from_str::<Value>(r#"{"keywords": {"令": "const"}}"#).unwrap();
+        let keywords = parse(&value).unwrap();
+
+        assert_eq!(keywords.lookup("令"), Some(Token::Const));
+        assert_eq!(keywords.lookup("给"), Some(Token::Let));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let tests = vec![
+            (r#"{"base": "klingon"}"#, "unknown base pack `klingon`"),
+            (r#"{"keywords": {"甲": "loop"}}"#, "unknown keyword `loop` for `甲`"),
+            (r#"{"keywords": {"甲": 1}}"#, "`keywords.甲` should be a string"),
+            (r#"{"remove": "fn"}"#, "`remove` should be a list of strings"),
+        ];
+
+        for (input, expect) in tests {
+            let value = serde_json::from_str::<Value>(input).unwrap();
+            assert_eq!(parse(&value), Err(String::from(expect)));
+        }
+    }
+}
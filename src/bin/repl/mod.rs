@@ -2,28 +2,130 @@
 extern crate rustyline;
 
 pub mod helper;
+pub mod keywords;
 
-use three_body_interpreter::evaluator::builtins::new_builtins;
+use three_body_interpreter::diagnostics::Diagnostic;
+use three_body_interpreter::evaluator::builtins::new_builtins_with;
 use three_body_interpreter::evaluator::env;
 use three_body_interpreter::evaluator::Evaluator;
 use three_body_interpreter::evaluator::object;
 use three_body_interpreter::lexer::Lexer;
-use three_body_interpreter::parser::Parser;
+use three_body_interpreter::parser::{ParseErrors, Parser};
+use three_body_interpreter::token::keyword::KeywordSet;
+use three_body_interpreter::token::Span;
+use three_body_interpreter::translator::{translate, Spelling};
 use std::cell::RefCell;
+use std::io::{IsTerminal, Read};
 use std::rc::Rc;
 use std::fs;
 
+fn print_parse_errors(errors: &ParseErrors, file_name: &str, source: &str) {
+    let colored = std::io::stdout().is_terminal();
+    for err in errors {
+        println!("{}\n", Diagnostic::from(err).render(file_name, source, colored));
+    }
+}
+
+fn print_evaluated(evaluator: &Evaluator, evaluated: object::Object, file_name: &str, source: &str) {
+    match evaluated {
+        object::Object::Null => {},
+        object::Object::Error(msg) => {
+            let colored = std::io::stdout().is_terminal();
+            let diagnostic = Diagnostic::error(msg).with_span(evaluator.error_span);
+            println!("{}\n", diagnostic.render(file_name, source, colored));
+        }
+        _ => println!("{}\n", evaluated.repr()),
+    }
+}
+
+
+/// take `name <value>` out of args, an error when the name is there but the value is not
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    let Some(i) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    match args.get(i + 1) {
+        Some(value) if !value.starts_with("--") => {
+            let value = args.remove(i + 1);
+            args.remove(i);
+            Ok(Some(value))
+        }
+        _ => Err(format!("missing value for {}", name)),
+    }
+}
+
+/// take a `name` switch out of args
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|arg| arg == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 3body translate --to zh|en [file], reads stdin without a file
+fn run_translate(mut args: Vec<String>) {
+    let to = match take_option(&mut args, "--to") {
+        Ok(Some(to)) => Spelling::by_name(&to),
+        Ok(None) => None,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            None
+        }
+    };
+    let Some(to) = to else {
+        eprintln!("usage: 3body translate --to zh|zh-hant|en [file]");
+        std::process::exit(2);
+    };
+
+    let source = match args.get(2) {
+        Some(path) => fs::read_to_string(path),
+        None => {
+            let mut source = String::new();
+            std::io::stdin().read_to_string(&mut source).map(|_| source)
+        }
+    };
+
+    match source {
+        Ok(source) => print!("{}", translate(&source, to)),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
 
 fn main() {
-    let mut rl = rustyline::Editor::new().expect("should exist");
+    let mut args: Vec<String> = std::env::args().collect();
 
-    rl.set_helper(Some(helper::Helper::new()));
+    if args.get(1).map(String::as_str) == Some("translate") {
+        run_translate(args);
+        return;
+    }
 
-    let mut evaluator = Evaluator {
-        env: Rc::new(RefCell::new(env::Env::from(new_builtins()))),
+    let keywords = match take_option(&mut args, "--keywords") {
+        Ok(Some(pack)) => match keywords::load(&pack) {
+            Ok(keywords) => keywords,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                std::process::exit(1);
+            }
+        },
+        Ok(None) => KeywordSet::default(),
+        Err(err) => {
+            eprintln!("error: {}\nusage: 3body --keywords simplified|traditional|english|<path> ...", err);
+            std::process::exit(2);
+        }
     };
+    let strict = take_flag(&mut args, "--strict");
 
-    let args: Vec<String> = std::env::args().collect();
+    let mut rl = rustyline::Editor::new().expect("should exist");
+
+    rl.set_helper(Some(helper::Helper::new(&keywords, strict)));
+
+    let mut evaluator = Evaluator::new(Rc::new(RefCell::new(env::Env::from(new_builtins_with(&keywords)))));
 
     if args.len() > 1 {
         match args[1].as_str() {
@@ -32,23 +134,19 @@ fn main() {
             }
             "-c" => {
                 let input = args[2].to_owned();
-                let mut lexer = Lexer::new(&input);
+                let mut lexer = Lexer::with_keywords(&input, keywords.clone());
+                lexer.set_strict(strict);
                 let mut parser = Parser::new(lexer);
                 let program = parser.parse();
                 let errors = parser.get_errors();
 
                 if errors.len() > 0 {
-                    for err in errors {
-                        println!("{:?}", err);
-                    }
+                    print_parse_errors(&errors, "<command>", &input);
                     return;
                 }
 
                 if let Some(evaluated) = evaluator.eval(&program) {
-                    match evaluated {
-                        object::Object::Null => {},
-                        _ => println!("{}\n", evaluated),
-                    }
+                    print_evaluated(&evaluator, evaluated, "<command>", &input);
                 }
             }
             "-h" => {
@@ -60,27 +158,29 @@ Options and arguments:
 -h     : print this help message and exit 
 -c cmd : program passed in as string (terminates option list)
 -      : program in repl (default)
+
+translate --to zh|zh-hant|en [file] : translate keywords and builtin names of a script
+
+--keywords pack : keyword pack, simplified (default), traditional, english
+                  or the path of a TOML / JSON pack
+--strict        : reject full-width punctuation and digits like 【 ， １
 ")
             },
             path => {
                 let contents = fs::read_to_string(path).expect("Should have been able to read the file");
-                let mut lexer = Lexer::new(&contents);
+                let mut lexer = Lexer::with_keywords(&contents, keywords.clone());
+                lexer.set_strict(strict);
                 let mut parser = Parser::new(lexer);
                 let program = parser.parse();
                 let errors = parser.get_errors();
 
                 if errors.len() > 0 {
-                    for err in errors {
-                        println!("{:?}", err);
-                    }
+                    print_parse_errors(&errors, path, &contents);
                     return;
                 }
 
                 if let Some(evaluated) = evaluator.eval(&program) {
-                    match evaluated {
-                        object::Object::Null => {},
-                        _ => println!("{}\n", evaluated),
-                    }
+                    print_evaluated(&evaluator, evaluated, path, &contents);
                 }
             }
         }
@@ -89,27 +189,34 @@ Options and arguments:
 
     println!("欢迎来到三体世界！");
 
+    // every line entered so far, each one is lexed as a continuation of it,
+    // so an error in a function declared lines ago still points at that line
+    let mut session = String::new();
+
     loop {
         match rl.readline(">> ") {
             Ok(line) => {
                 rl.add_history_entry(&line);
-                let mut lexer = Lexer::new(&line);
+                if !session.is_empty() {
+                    session.push('\n');
+                }
+                let origin = Span::new(session.len(), session.len(), session.matches('\n').count() + 1, 1);
+                session.push_str(&line);
+
+                let mut lexer = Lexer::with_keywords(&line, keywords.clone());
+                lexer.set_strict(strict);
+                lexer.set_origin(origin);
                 let mut parser = Parser::new(lexer);
                 let program = parser.parse();
                 let errors = parser.get_errors();
 
                 if errors.len() > 0 {
-                    for err in errors {
-                        println!("{:?}", err);
-                    }
+                    print_parse_errors(&errors, "<repl>", &session);
                     continue;
                 }
 
                 if let Some(evaluated) = evaluator.eval(&program) {
-                    match evaluated {
-                        object::Object::Null => {},
-                        _ => println!("{}\n", evaluated),
-                    }
+                    print_evaluated(&evaluator, evaluated, "<repl>", &session);
                 }
             }
             Err(rustyline::error::ReadlineError::Interrupted) => {
@@ -126,3 +233,41 @@ Options and arguments:
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use three_body_interpreter::token::keyword::aliases;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_take_option() {
+        let mut rest = args(&["3body", "--keywords", "english", "a.3body"]);
+        assert_eq!(take_option(&mut rest, "--keywords"), Ok(Some(String::from("english"))));
+        assert_eq!(rest, args(&["3body", "a.3body"]));
+
+        assert_eq!(take_option(&mut rest, "--keywords"), Ok(None));
+
+        for input in [&["3body", "--keywords"][..], &["3body", "--keywords", "--strict"][..]] {
+            let mut rest = args(input);
+            assert_eq!(
+                take_option(&mut rest, "--keywords"),
+                Err(String::from("missing value for --keywords"))
+            );
+        }
+    }
+
+    /// the readme belongs to this package, so the keyword table is checked from here
+    #[test]
+    fn test_aliases_are_documented() {
+        let readme = include_str!("../../../README.md");
+        for keyword in aliases() {
+            let english = keyword.english.replace('|', "\\|");
+            let row = format!("|{}|{}|{:?}|", english, keyword.word, keyword.meaning);
+            assert!(readme.contains(&row), "README.md is missing the row {}", row);
+        }
+    }
+}